@@ -101,13 +101,55 @@ fn enable_neon() {
     }
 }
 
-/// Enables the Snoop Control Unit (SCU).
-pub fn enable_scu() {
-    let cbar = regs::cbar();
-    let scu_ctrl = cbar;
-
+/// Sets the stack pointers of the IRQ, FIQ, Abort and Undefined modes.
+///
+/// The CPU is switched to each mode in turn to set its banked stack pointer and returned
+/// to the original mode afterwards. Each pointer is the initial stack top, stacks grow
+/// downwards.
+///
+/// This function must be called from a privileged mode other than the ones being set up,
+/// typically SVC, early in boot before interrupts or exceptions can occur.
+///
+/// # Safety
+///
+/// The pointers must be 8-byte aligned tops of memory regions large enough for the
+/// respective mode and not used for anything else.
+pub unsafe fn set_mode_stacks(irq: *mut u8, fiq: *mut u8, abort: *mut u8, undef: *mut u8) {
+    // Only r0-r4 are used, because r8-r12 are banked in FIQ mode.
     unsafe {
-        let value = core::ptr::read_volatile(scu_ctrl as *const u32);
-        core::ptr::write_volatile(scu_ctrl as *mut u32, value | 1);
+        asm! {
+            "mrs    r4, cpsr",
+            "cps    #0x12", // IRQ mode.
+            "mov    sp, r0",
+            "cps    #0x11", // FIQ mode.
+            "mov    sp, r1",
+            "cps    #0x17", // Abort mode.
+            "mov    sp, r2",
+            "cps    #0x1B", // Undefined mode.
+            "mov    sp, r3",
+            "msr    cpsr_c, r4",
+            "isb",
+            in("r0") irq,
+            in("r1") fiq,
+            in("r2") abort,
+            in("r3") undef,
+            out("r4") _,
+        }
     }
 }
+
+/// Adds the current core to the data cache coherency.
+///
+/// Unlike the Cortex-A9, the Cortex-A7 has no memory-mapped SCU registers. Coherency is
+/// controlled only through ACTLR.SMP, so this is the same as `enable_smp`.
+#[deprecated(note = "the Cortex-A7 has no memory-mapped SCU, use `enable_smp` instead")]
+pub fn enable_scu() {
+    enable_smp();
+}
+
+/// Sets the SMP bit in ACTLR, so the current core takes part in the data cache coherency.
+///
+/// This must be called by each core before enabling its data cache.
+pub fn enable_smp() {
+    regs::set_actlr(regs::actlr() | (1 << 6));
+}
@@ -43,18 +43,21 @@ bitflags! {
     }
 }
 
-/// Initializes a translation table.
-///
-/// The `map_fn` takes an address and returns the appropriate `MemoryRegion` for it.
-pub fn init_translation_table<F>(table: &mut TranslationTable, map_fn: F)
-where
-    F: Fn(u32) -> MemoryRegion,
-{
-    for (i, table_entry) in table.iter_mut().enumerate().take(TRANSLATION_TABLE_LENGTH) {
-        let addr = (i as u32) << 20;
-        let region = map_fn(addr);
-
-        let attr = match region {
+impl MemoryRegion {
+    /// Returns the attribute bits of a short-descriptor section entry for the region.
+    ///
+    /// TEX is always 0b000 and the domain is 0. The bits are encoded as follows:
+    ///
+    /// | Region           | C | B | S | AP   | XN | Memory type                        |
+    /// |------------------|---|---|---|------|----|------------------------------------|
+    /// | `Code`           | 1 | 1 | 1 | 0b11 | 0  | Normal, write-back, no allocate    |
+    /// | `Data`           | 1 | 1 | 1 | 0b11 | 1  | Normal, write-back, no allocate    |
+    /// | `UnbufferedData` | 1 | 0 | 1 | 0b11 | 1  | Normal, write-through, no allocate |
+    /// | `Device`         | 0 | 1 | 0 | 0b11 | 1  | Shareable device                   |
+    ///
+    /// The returned value doesn't include the section base address and entry type bits.
+    pub fn section_attributes(&self) -> u32 {
+        match self {
             // Code is normal memory with execute permissions.
             MemoryRegion::Code => (SectionAttr::Shareable
                 | SectionAttr::Cacheable
@@ -77,12 +80,25 @@ where
                 | SectionAttr::ExecuteNever)
                 .bits(),
 
-            // Default is device memory without execute permissions.
+            // Device memory without execute permissions.
             MemoryRegion::Device => {
                 (SectionAttr::Bufferable | SectionAttr::ApFullAccess | SectionAttr::ExecuteNever)
                     .bits()
             }
-        };
+        }
+    }
+}
+
+/// Initializes a translation table.
+///
+/// The `map_fn` takes an address and returns the appropriate `MemoryRegion` for it.
+pub fn init_translation_table<F>(table: &mut TranslationTable, map_fn: F)
+where
+    F: Fn(u32) -> MemoryRegion,
+{
+    for (i, table_entry) in table.iter_mut().enumerate().take(TRANSLATION_TABLE_LENGTH) {
+        let addr = (i as u32) << 20;
+        let attr = map_fn(addr).section_attributes();
 
         *table_entry = addr | attr | L1_ENTRY_SECTION;
     }
@@ -92,19 +108,7 @@ where
 ///
 /// The translation table must be initialized before calling this function.
 pub fn enable(table: &TranslationTable) {
-    // Set SMP bit in ACTLR
-    unsafe {
-        let mut auxctrl: u32;
-        asm! {
-            "mrc p15, 0, {r}, c1, c0,  1",
-            r = out(reg) auxctrl
-        }
-        auxctrl |= 1 << 6;
-        asm! {
-            "mcr p15, 0, {r}, c1, c0,  1",
-            r = in(reg) auxctrl
-        }
-    }
+    crate::enable_smp();
 
     // Set domain 0 to client
     unsafe {
@@ -73,6 +73,30 @@ pub fn set_dccisw(value: u32) {
     }
 }
 
+/// Return ACTLR register value.
+pub fn actlr() -> u32 {
+    let mut result: u32;
+    unsafe {
+        asm! {
+            "mrc p15, 0, {r}, c1, c0, 1",
+            r = out(reg) result
+        }
+    }
+
+    result
+}
+
+/// Set ACTLR register value.
+pub fn set_actlr(value: u32) {
+    unsafe {
+        asm! {
+            "mcr p15, 0, {r}, c1, c0, 1",
+            "isb",
+            r = in(reg) value
+        }
+    }
+}
+
 /// Return CBAR register value.
 pub fn cbar() -> u32 {
     let mut result: u32;
@@ -98,3 +122,37 @@ pub fn mpidr() -> u32 {
 
     result
 }
+
+/// Set CNTP_TVAL register value.
+pub fn set_cntp_tval(value: u32) {
+    unsafe {
+        asm! {
+            "mcr p15, 0, {r}, c14, c2, 0",
+            r = in(reg) value
+        }
+    }
+}
+
+/// Return CNTP_CTL register value.
+pub fn cntp_ctl() -> u32 {
+    let mut result: u32;
+    unsafe {
+        asm! {
+            "mrc p15, 0, {r}, c14, c2, 1",
+            r = out(reg) result
+        }
+    }
+
+    result
+}
+
+/// Set CNTP_CTL register value.
+pub fn set_cntp_ctl(value: u32) {
+    unsafe {
+        asm! {
+            "mcr p15, 0, {r}, c14, c2, 1",
+            "isb",
+            r = in(reg) value
+        }
+    }
+}
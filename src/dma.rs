@@ -1,6 +1,11 @@
 //! Direct memory access controller.
 
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
 use cfg_if::cfg_if;
+use critical_section::Mutex;
 
 use crate::pac;
 
@@ -62,6 +67,10 @@ pub struct DmaStreamConfig {
     pub bufferable_transfers: bool,
     /// Current target for double-buffer mode.
     pub current_target: CurrentTarget,
+    /// FIFO mode, direct mode is used when disabled.
+    pub fifo_mode: bool,
+    /// FIFO threshold level, only used in FIFO mode.
+    pub fifo_threshold: FifoThreshold,
 }
 
 impl Default for DmaStreamConfig {
@@ -86,10 +95,97 @@ impl Default for DmaStreamConfig {
             peripheral_burst_transfer: BurstTransfer::Single,
             bufferable_transfers: false,
             current_target: CurrentTarget::Memory0,
+            fifo_mode: false,
+            fifo_threshold: FifoThreshold::Half,
         }
     }
 }
 
+impl DmaStreamConfig {
+    /// Checks the configuration for invalid combinations of transfer direction,
+    /// burst transfers, data sizes and FIFO threshold.
+    pub fn validate(&self) -> Result<(), DmaConfigError> {
+        if self.transfer_direction == TransferDirection::MemoryToMemory {
+            if !self.fifo_mode {
+                return Err(DmaConfigError::MemoryToMemoryDirectMode);
+            }
+            if self.circular || self.double_buffer {
+                return Err(DmaConfigError::MemoryToMemoryCircular);
+            }
+        }
+
+        if !self.fifo_mode {
+            // In direct mode, bursts are forced to single transfers and the memory data size
+            // is forced to the peripheral data size by hardware.
+            if self.memory_burst_transfer != BurstTransfer::Single
+                || self.peripheral_burst_transfer != BurstTransfer::Single
+            {
+                return Err(DmaConfigError::BurstInDirectMode);
+            }
+            if self.memory_data_size != self.peripheral_data_size {
+                return Err(DmaConfigError::DataSizeMismatch);
+            }
+            return Ok(());
+        }
+
+        // The FIFO threshold must be a multiple of the memory burst size.
+        let threshold_bytes = self.fifo_threshold.bytes();
+        let memory_burst_bytes = self.memory_burst_transfer.beats() * self.memory_data_size.bytes();
+        if memory_burst_bytes > threshold_bytes
+            || !threshold_bytes.is_multiple_of(memory_burst_bytes)
+        {
+            return Err(DmaConfigError::FifoThreshold);
+        }
+
+        // A peripheral burst must fit into the FIFO.
+        let peripheral_burst_bytes =
+            self.peripheral_burst_transfer.beats() * self.peripheral_data_size.bytes();
+        if peripheral_burst_bytes > FIFO_SIZE {
+            return Err(DmaConfigError::PeripheralBurstSize);
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a memory address is suitable for the configured memory burst.
+    ///
+    /// Bursts must not cross a 1KB address boundary, which is guaranteed when
+    /// the address is aligned to the burst size.
+    pub fn validate_memory_address(&self, address: u32) -> Result<(), DmaConfigError> {
+        let memory_burst_bytes = self.memory_burst_transfer.beats() * self.memory_data_size.bytes();
+        if self.memory_burst_transfer != BurstTransfer::Single
+            && !address.is_multiple_of(memory_burst_bytes)
+        {
+            return Err(DmaConfigError::BurstBoundary);
+        }
+
+        Ok(())
+    }
+}
+
+/// FIFO size in bytes.
+const FIFO_SIZE: u32 = 16;
+
+/// Configuration errors.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DmaConfigError {
+    /// Memory-to-memory transfers require FIFO mode.
+    MemoryToMemoryDirectMode,
+    /// Memory-to-memory transfers can't be used with circular or double-buffer mode.
+    MemoryToMemoryCircular,
+    /// Burst transfers require FIFO mode.
+    BurstInDirectMode,
+    /// Memory and peripheral data size must be equal in direct mode.
+    DataSizeMismatch,
+    /// FIFO threshold is not compatible with memory burst and data size.
+    FifoThreshold,
+    /// Peripheral burst exceeds the FIFO size.
+    PeripheralBurstSize,
+    /// Memory burst would cross a 1KB address boundary.
+    BurstBoundary,
+}
+
 /// Data transfer direction.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
@@ -126,6 +222,17 @@ impl From<DataSize> for u8 {
     }
 }
 
+impl DataSize {
+    /// Returns the size in bytes.
+    pub fn bytes(&self) -> u32 {
+        match self {
+            DataSize::Byte => 1,
+            DataSize::HalfWord => 2,
+            DataSize::Word => 4,
+        }
+    }
+}
+
 /// Priority level.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
@@ -166,6 +273,45 @@ impl From<BurstTransfer> for u8 {
     }
 }
 
+impl BurstTransfer {
+    /// Returns the number of beats.
+    pub fn beats(&self) -> u32 {
+        match self {
+            BurstTransfer::Single => 1,
+            BurstTransfer::Incremental4 => 4,
+            BurstTransfer::Incremental8 => 8,
+            BurstTransfer::Incremental16 => 16,
+        }
+    }
+}
+
+/// FIFO threshold level.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum FifoThreshold {
+    /// 1/4 full FIFO.
+    Quarter = 0b00,
+    /// 1/2 full FIFO.
+    Half = 0b01,
+    /// 3/4 full FIFO.
+    ThreeQuarters = 0b10,
+    /// Full FIFO.
+    Full = 0b11,
+}
+
+impl From<FifoThreshold> for u8 {
+    fn from(value: FifoThreshold) -> Self {
+        value as u8
+    }
+}
+
+impl FifoThreshold {
+    /// Returns the threshold in bytes.
+    pub fn bytes(&self) -> u32 {
+        (*self as u32 + 1) * FIFO_SIZE / 4
+    }
+}
+
 /// Current target for double-buffer mode.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
@@ -182,8 +328,76 @@ impl From<CurrentTarget> for bool {
     }
 }
 
+/// Transfer errors.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Error {
+    /// Transfer error, e.g. a bus error.
+    Transfer,
+    /// FIFO overrun or underrun.
+    Fifo,
+    /// Direct mode error.
+    DirectMode,
+}
+
+/// DMA controllers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DmaController {
+    /// DMA1.
+    Dma1,
+    /// DMA2.
+    Dma2,
+}
+
+/// Mask of all flags of a stream in the status and flag clear registers.
+///
+/// FEIF (bit 0), DMEIF (bit 2), TEIF (bit 3), HTIF (bit 4) and TCIF (bit 5).
+const FLAG_MASK_ALL: u32 = 0b111101;
+
+/// Interrupt enable bits TCIE (bit 4), TEIE (bit 2) and DMEIE (bit 1) in the stream
+/// configuration register used by `wait_complete_async`.
+const CR_WAIT_INTERRUPTS: u32 = 0b10110;
+
+/// Interrupt enable bits HTIE (bit 3), TEIE (bit 2) and DMEIE (bit 1) in the stream
+/// configuration register used by `wait_half_async`.
+const CR_WAIT_HALF_INTERRUPTS: u32 = 0b01110;
+
+/// FIFO error interrupt enable bit FEIE (bit 7) in the stream FIFO control register used by
+/// `wait_complete_async` and `wait_half_async`.
+const FCR_WAIT_INTERRUPTS: u32 = 1 << 7;
+
+/// Wakers of the streams waiting for transfer events.
+static WAKERS: Mutex<RefCell<[Option<Waker>; 16]>> = Mutex::new(RefCell::new([const { None }; 16]));
+
+/// Transfer complete flag in the status and flag clear registers.
+const FLAG_TC: u32 = 1 << 5;
+
+/// Half transfer flag in the status and flag clear registers.
+const FLAG_HT: u32 = 1 << 4;
+
+/// All DMA streams in order.
+const ALL_STREAMS: [DmaStream; 16] = [
+    DmaStream::Dma1Stream0,
+    DmaStream::Dma1Stream1,
+    DmaStream::Dma1Stream2,
+    DmaStream::Dma1Stream3,
+    DmaStream::Dma1Stream4,
+    DmaStream::Dma1Stream5,
+    DmaStream::Dma1Stream6,
+    DmaStream::Dma1Stream7,
+    DmaStream::Dma2Stream0,
+    DmaStream::Dma2Stream1,
+    DmaStream::Dma2Stream2,
+    DmaStream::Dma2Stream3,
+    DmaStream::Dma2Stream4,
+    DmaStream::Dma2Stream5,
+    DmaStream::Dma2Stream6,
+    DmaStream::Dma2Stream7,
+];
+
 /// DMA streams.
 #[derive(Debug, Clone, Copy)]
+#[repr(u8)]
 pub enum DmaStream {
     /// DMA1 stream 0.
     Dma1Stream0,
@@ -221,7 +435,7 @@ pub enum DmaStream {
 }
 
 macro_rules! dma_stream_configure {
-    ($dma: ident, $dma_cr: ident, $dmamux:ident, $dmamux_cr: ident, $config: ident) => {
+    ($dma: ident, $dma_cr: ident, $dma_fcr: ident, $dmamux:ident, $dmamux_cr: ident, $config: ident) => {
         unsafe {
             let regs = &(*pac::$dma::ptr());
             regs.$dma_cr.modify(|_, w| {
@@ -268,6 +482,13 @@ macro_rules! dma_stream_configure {
                 regs.$dma_cr.modify(|r, w| w.bits(r.bits() & !(1 << 20)));
             }
 
+            regs.$dma_fcr.modify(|_, w| {
+                w.dmdis()
+                    .bit($config.fifo_mode)
+                    .fth()
+                    .bits($config.fifo_threshold.into())
+            });
+
             let regs = &(*pac::$dmamux::ptr());
             regs.$dmamux_cr
                 .modify(|_, w| w.dmareq_id().bits($config.request_input.into()));
@@ -286,61 +507,70 @@ macro_rules! dma_stream_enable {
 
 impl DmaStream {
     /// Initializes the stream with a configuration.
-    pub fn init(&self, config: DmaStreamConfig) {
+    ///
+    /// Returns an error if the configuration is invalid, see `DmaStreamConfig::validate()`.
+    pub fn init(&self, config: DmaStreamConfig) -> Result<(), DmaConfigError> {
+        config.validate()?;
+
         match self {
             DmaStream::Dma1Stream0 => {
-                dma_stream_configure!(DMA1, dma_s0cr, DMAMUX1, dmamux_c0cr, config);
+                dma_stream_configure!(DMA1, dma_s0cr, dma_s0fcr, DMAMUX1, dmamux_c0cr, config);
             }
             DmaStream::Dma1Stream1 => {
-                dma_stream_configure!(DMA1, dma_s1cr, DMAMUX1, dmamux_c1cr, config);
+                dma_stream_configure!(DMA1, dma_s1cr, dma_s1fcr, DMAMUX1, dmamux_c1cr, config);
             }
             DmaStream::Dma1Stream2 => {
-                dma_stream_configure!(DMA1, dma_s2cr, DMAMUX1, dmamux_c2cr, config);
+                dma_stream_configure!(DMA1, dma_s2cr, dma_s2fcr, DMAMUX1, dmamux_c2cr, config);
             }
             DmaStream::Dma1Stream3 => {
-                dma_stream_configure!(DMA1, dma_s3cr, DMAMUX1, dmamux_c3cr, config);
+                dma_stream_configure!(DMA1, dma_s3cr, dma_s3fcr, DMAMUX1, dmamux_c3cr, config);
             }
             DmaStream::Dma1Stream4 => {
-                dma_stream_configure!(DMA1, dma_s4cr, DMAMUX1, dmamux_c4cr, config);
+                dma_stream_configure!(DMA1, dma_s4cr, dma_s4fcr, DMAMUX1, dmamux_c4cr, config);
             }
             DmaStream::Dma1Stream5 => {
-                dma_stream_configure!(DMA1, dma_s5cr, DMAMUX1, dmamux_c5cr, config);
+                dma_stream_configure!(DMA1, dma_s5cr, dma_s5fcr, DMAMUX1, dmamux_c5cr, config);
             }
             DmaStream::Dma1Stream6 => {
-                dma_stream_configure!(DMA1, dma_s6cr, DMAMUX1, dmamux_c6cr, config);
+                dma_stream_configure!(DMA1, dma_s6cr, dma_s6fcr, DMAMUX1, dmamux_c6cr, config);
             }
             DmaStream::Dma1Stream7 => {
-                dma_stream_configure!(DMA1, dma_s7cr, DMAMUX1, dmamux_c7cr, config);
+                dma_stream_configure!(DMA1, dma_s7cr, dma_s7fcr, DMAMUX1, dmamux_c7cr, config);
             }
 
             DmaStream::Dma2Stream0 => {
-                dma_stream_configure!(DMA2, dma_s0cr, DMAMUX1, dmamux_c8cr, config);
+                dma_stream_configure!(DMA2, dma_s0cr, dma_s0fcr, DMAMUX1, dmamux_c8cr, config);
             }
             DmaStream::Dma2Stream1 => {
-                dma_stream_configure!(DMA2, dma_s1cr, DMAMUX1, dmamux_c9cr, config);
+                dma_stream_configure!(DMA2, dma_s1cr, dma_s1fcr, DMAMUX1, dmamux_c9cr, config);
             }
             DmaStream::Dma2Stream2 => {
-                dma_stream_configure!(DMA2, dma_s2cr, DMAMUX1, dmamux_c10cr, config);
+                dma_stream_configure!(DMA2, dma_s2cr, dma_s2fcr, DMAMUX1, dmamux_c10cr, config);
             }
             DmaStream::Dma2Stream3 => {
-                dma_stream_configure!(DMA2, dma_s3cr, DMAMUX1, dmamux_c11cr, config);
+                dma_stream_configure!(DMA2, dma_s3cr, dma_s3fcr, DMAMUX1, dmamux_c11cr, config);
             }
             DmaStream::Dma2Stream4 => {
-                dma_stream_configure!(DMA2, dma_s4cr, DMAMUX1, dmamux_c12cr, config);
+                dma_stream_configure!(DMA2, dma_s4cr, dma_s4fcr, DMAMUX1, dmamux_c12cr, config);
             }
             DmaStream::Dma2Stream5 => {
-                dma_stream_configure!(DMA2, dma_s5cr, DMAMUX1, dmamux_c13cr, config);
+                dma_stream_configure!(DMA2, dma_s5cr, dma_s5fcr, DMAMUX1, dmamux_c13cr, config);
             }
             DmaStream::Dma2Stream6 => {
-                dma_stream_configure!(DMA2, dma_s6cr, DMAMUX1, dmamux_c14cr, config);
+                dma_stream_configure!(DMA2, dma_s6cr, dma_s6fcr, DMAMUX1, dmamux_c14cr, config);
             }
             DmaStream::Dma2Stream7 => {
-                dma_stream_configure!(DMA2, dma_s7cr, DMAMUX1, dmamux_c15cr, config);
+                dma_stream_configure!(DMA2, dma_s7cr, dma_s7fcr, DMAMUX1, dmamux_c15cr, config);
             }
         }
+
+        Ok(())
     }
 
     /// Starts the transfer.
+    ///
+    /// With memory bursts, the address has to be checked before with
+    /// `DmaStreamConfig::validate_memory_address`.
     pub fn start_transfer(
         &self,
         memory_address: impl Into<u32>,
@@ -444,6 +674,33 @@ impl DmaStream {
         self.disable();
     }
 
+    /// Returns the number of data items remaining to be transferred (NDTR).
+    ///
+    /// In circular mode, the value is reloaded when reaching 0.
+    pub fn remaining_transfers(&self) -> u32 {
+        let dma1 = unsafe { &(*pac::DMA1::ptr()) };
+        let dma2 = unsafe { &(*pac::DMA2::ptr()) };
+        match self {
+            DmaStream::Dma1Stream0 => dma1.dma_s0ndtr.read().bits(),
+            DmaStream::Dma1Stream1 => dma1.dma_s1ndtr.read().bits(),
+            DmaStream::Dma1Stream2 => dma1.dma_s2ndtr.read().bits(),
+            DmaStream::Dma1Stream3 => dma1.dma_s3ndtr.read().bits(),
+            DmaStream::Dma1Stream4 => dma1.dma_s4ndtr.read().bits(),
+            DmaStream::Dma1Stream5 => dma1.dma_s5ndtr.read().bits(),
+            DmaStream::Dma1Stream6 => dma1.dma_s6ndtr.read().bits(),
+            DmaStream::Dma1Stream7 => dma1.dma_s7ndtr.read().bits(),
+
+            DmaStream::Dma2Stream0 => dma2.dma_s0ndtr.read().bits(),
+            DmaStream::Dma2Stream1 => dma2.dma_s1ndtr.read().bits(),
+            DmaStream::Dma2Stream2 => dma2.dma_s2ndtr.read().bits(),
+            DmaStream::Dma2Stream3 => dma2.dma_s3ndtr.read().bits(),
+            DmaStream::Dma2Stream4 => dma2.dma_s4ndtr.read().bits(),
+            DmaStream::Dma2Stream5 => dma2.dma_s5ndtr.read().bits(),
+            DmaStream::Dma2Stream6 => dma2.dma_s6ndtr.read().bits(),
+            DmaStream::Dma2Stream7 => dma2.dma_s7ndtr.read().bits(),
+        }
+    }
+
     /// Enables the stream.
     pub fn enable(&self) {
         self.clear_all_flags();
@@ -556,6 +813,64 @@ impl DmaStream {
         }
     }
 
+    /// Returns if the stream is enabled, i.e. a transfer is running.
+    pub fn is_enabled(&self) -> bool {
+        let dma1 = unsafe { &(*pac::DMA1::ptr()) };
+        let dma2 = unsafe { &(*pac::DMA2::ptr()) };
+        match self {
+            DmaStream::Dma1Stream0 => dma1.dma_s0cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream1 => dma1.dma_s1cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream2 => dma1.dma_s2cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream3 => dma1.dma_s3cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream4 => dma1.dma_s4cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream5 => dma1.dma_s5cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream6 => dma1.dma_s6cr.read().en().bit_is_set(),
+            DmaStream::Dma1Stream7 => dma1.dma_s7cr.read().en().bit_is_set(),
+
+            DmaStream::Dma2Stream0 => dma2.dma_s0cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream1 => dma2.dma_s1cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream2 => dma2.dma_s2cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream3 => dma2.dma_s3cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream4 => dma2.dma_s4cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream5 => dma2.dma_s5cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream6 => dma2.dma_s6cr.read().en().bit_is_set(),
+            DmaStream::Dma2Stream7 => dma2.dma_s7cr.read().en().bit_is_set(),
+        }
+    }
+
+    /// Returns the memory target currently used in double-buffer mode.
+    ///
+    /// The other memory target is not accessed by the stream and can be refilled.
+    pub fn current_target(&self) -> CurrentTarget {
+        let dma1 = unsafe { &(*pac::DMA1::ptr()) };
+        let dma2 = unsafe { &(*pac::DMA2::ptr()) };
+        let ct = match self {
+            DmaStream::Dma1Stream0 => dma1.dma_s0cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream1 => dma1.dma_s1cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream2 => dma1.dma_s2cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream3 => dma1.dma_s3cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream4 => dma1.dma_s4cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream5 => dma1.dma_s5cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream6 => dma1.dma_s6cr.read().ct().bit_is_set(),
+            DmaStream::Dma1Stream7 => dma1.dma_s7cr.read().ct().bit_is_set(),
+
+            DmaStream::Dma2Stream0 => dma2.dma_s0cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream1 => dma2.dma_s1cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream2 => dma2.dma_s2cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream3 => dma2.dma_s3cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream4 => dma2.dma_s4cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream5 => dma2.dma_s5cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream6 => dma2.dma_s6cr.read().ct().bit_is_set(),
+            DmaStream::Dma2Stream7 => dma2.dma_s7cr.read().ct().bit_is_set(),
+        };
+
+        if ct {
+            CurrentTarget::Memory1
+        } else {
+            CurrentTarget::Memory0
+        }
+    }
+
     /// Returns the transfer complete flag.
     pub fn is_transfer_complete(&self) -> bool {
         let dma1 = unsafe { &(*pac::DMA1::ptr()) };
@@ -681,13 +996,252 @@ impl DmaStream {
         }
     }
 
-    /// Clears all flags.
+    /// Clears all flags with a single write to the flag clear register.
     pub fn clear_all_flags(&self) {
-        self.clear_transfer_complete();
-        self.clear_half_transfer();
-        self.clear_transfer_error();
-        self.clear_fifo_error();
-        self.clear_direct_mode_error();
+        let mask = FLAG_MASK_ALL << self.flag_offset();
+        let high = self.stream_number() >= 4;
+
+        unsafe {
+            let dma1 = &(*pac::DMA1::ptr());
+            let dma2 = &(*pac::DMA2::ptr());
+            match (self.is_dma2(), high) {
+                (false, false) => dma1.dma_lifcr.write(|w| w.bits(mask)),
+                (false, true) => dma1.dma_hifcr.write(|w| w.bits(mask)),
+                (true, false) => dma2.dma_lifcr.write(|w| w.bits(mask)),
+                (true, true) => dma2.dma_hifcr.write(|w| w.bits(mask)),
+            }
+        }
+    }
+
+    /// Waits asynchronuously for the transfer to complete.
+    ///
+    /// The transfer complete, transfer error, direct mode error and FIFO error interrupts
+    /// of the stream are enabled while waiting. `on_interrupt()` must be called from the
+    /// interrupt handler of the stream to wake the task.
+    ///
+    /// The transfer complete flag is cleared when returning, so in circular or double-buffer
+    /// mode this can be called again to wait for the next pass through the buffer.
+    pub async fn wait_complete_async(&self) -> Result<(), Error> {
+        self.wait_event_async(
+            Self::is_transfer_complete,
+            Self::clear_transfer_complete,
+            CR_WAIT_INTERRUPTS,
+        )
+        .await
+    }
+
+    /// Waits asynchronuously for the first half of the transfer to complete.
+    ///
+    /// The half transfer, transfer error, direct mode error and FIFO error interrupts of
+    /// the stream are enabled while waiting. `on_interrupt()` must be called from the
+    /// interrupt handler of the stream to wake the task.
+    ///
+    /// The half transfer flag is cleared when returning. In circular mode, alternating
+    /// with `wait_complete_async()` allows refilling the half of the buffer that was
+    /// just consumed while the other half is transferred.
+    pub async fn wait_half_async(&self) -> Result<(), Error> {
+        self.wait_event_async(
+            Self::is_half_transfer,
+            Self::clear_half_transfer,
+            CR_WAIT_HALF_INTERRUPTS,
+        )
+        .await
+    }
+
+    /// Waits asynchronuously for an event flag, enabling the given interrupts while waiting.
+    async fn wait_event_async(
+        &self,
+        is_event: fn(&Self) -> bool,
+        clear_event: fn(&Self),
+        interrupts: u32,
+    ) -> Result<(), Error> {
+        poll_fn(|cx| {
+            let result = if self.is_transfer_error() {
+                Some(Err(Error::Transfer))
+            } else if self.is_direct_mode_error() {
+                Some(Err(Error::DirectMode))
+            } else if self.is_fifo_error() {
+                Some(Err(Error::Fifo))
+            } else if is_event(self) {
+                clear_event(self);
+                Some(Ok(()))
+            } else {
+                None
+            };
+
+            match result {
+                Some(result) => {
+                    self.modify_cr_bits(0, interrupts);
+                    self.modify_fcr_bits(0, FCR_WAIT_INTERRUPTS);
+                    Poll::Ready(result)
+                }
+                None => {
+                    critical_section::with(|cs| {
+                        WAKERS.borrow_ref_mut(cs)[*self as usize] = Some(cx.waker().clone());
+                    });
+                    self.modify_cr_bits(interrupts, 0);
+                    self.modify_fcr_bits(FCR_WAIT_INTERRUPTS, 0);
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Sets and clears bits in the stream configuration register.
+    fn modify_cr_bits(&self, set: u32, clear: u32) {
+        unsafe {
+            let dma1 = &(*pac::DMA1::ptr());
+            let dma2 = &(*pac::DMA2::ptr());
+            match self {
+                DmaStream::Dma1Stream0 => dma1
+                    .dma_s0cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream1 => dma1
+                    .dma_s1cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream2 => dma1
+                    .dma_s2cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream3 => dma1
+                    .dma_s3cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream4 => dma1
+                    .dma_s4cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream5 => dma1
+                    .dma_s5cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream6 => dma1
+                    .dma_s6cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream7 => dma1
+                    .dma_s7cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+
+                DmaStream::Dma2Stream0 => dma2
+                    .dma_s0cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream1 => dma2
+                    .dma_s1cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream2 => dma2
+                    .dma_s2cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream3 => dma2
+                    .dma_s3cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream4 => dma2
+                    .dma_s4cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream5 => dma2
+                    .dma_s5cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream6 => dma2
+                    .dma_s6cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream7 => dma2
+                    .dma_s7cr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+            }
+        }
+    }
+
+    /// Sets and clears bits in the stream FIFO control register.
+    fn modify_fcr_bits(&self, set: u32, clear: u32) {
+        unsafe {
+            let dma1 = &(*pac::DMA1::ptr());
+            let dma2 = &(*pac::DMA2::ptr());
+            match self {
+                DmaStream::Dma1Stream0 => dma1
+                    .dma_s0fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream1 => dma1
+                    .dma_s1fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream2 => dma1
+                    .dma_s2fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream3 => dma1
+                    .dma_s3fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream4 => dma1
+                    .dma_s4fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream5 => dma1
+                    .dma_s5fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream6 => dma1
+                    .dma_s6fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma1Stream7 => dma1
+                    .dma_s7fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+
+                DmaStream::Dma2Stream0 => dma2
+                    .dma_s0fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream1 => dma2
+                    .dma_s1fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream2 => dma2
+                    .dma_s2fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream3 => dma2
+                    .dma_s3fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream4 => dma2
+                    .dma_s4fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream5 => dma2
+                    .dma_s5fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream6 => dma2
+                    .dma_s6fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+                DmaStream::Dma2Stream7 => dma2
+                    .dma_s7fcr
+                    .modify(|r, w| w.bits((r.bits() & !clear) | set)),
+            }
+        }
+    }
+
+    /// Returns all flags of the stream from the status register, shifted to bit 0.
+    fn flags(&self) -> u32 {
+        let high = self.stream_number() >= 4;
+
+        let value = unsafe {
+            let dma1 = &(*pac::DMA1::ptr());
+            let dma2 = &(*pac::DMA2::ptr());
+            match (self.is_dma2(), high) {
+                (false, false) => dma1.dma_lisr.read().bits(),
+                (false, true) => dma1.dma_hisr.read().bits(),
+                (true, false) => dma2.dma_lisr.read().bits(),
+                (true, true) => dma2.dma_hisr.read().bits(),
+            }
+        };
+
+        (value >> self.flag_offset()) & FLAG_MASK_ALL
+    }
+
+    /// Returns the stream number 0-7 within the controller.
+    fn stream_number(&self) -> u8 {
+        (*self as u8) % 8
+    }
+
+    /// Returns if the stream belongs to DMA2.
+    fn is_dma2(&self) -> bool {
+        (*self as u8) >= 8
+    }
+
+    /// Returns the bit offset of the stream flags in the status and flag clear registers.
+    fn flag_offset(&self) -> u8 {
+        match self.stream_number() % 4 {
+            0 => 0,
+            1 => 6,
+            2 => 16,
+            _ => 22,
+        }
     }
 
     /// Clears the transfer compete error flag.
@@ -704,8 +1258,8 @@ impl DmaStream {
             DmaStream::Dma1Stream6 => dma1.dma_hifcr.write(|w| w.ctcif6().set_bit()),
             DmaStream::Dma1Stream7 => dma1.dma_hifcr.write(|w| w.ctcif7().set_bit()),
 
-            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.ctcif0().set_bit()),
-            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.ctcif1().set_bit()),
+            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.ctcif0().set_bit()),
+            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.ctcif1().set_bit()),
             DmaStream::Dma2Stream2 => dma2.dma_lifcr.write(|w| w.ctcif2().set_bit()),
             DmaStream::Dma2Stream3 => dma2.dma_lifcr.write(|w| w.ctcif3().set_bit()),
             DmaStream::Dma2Stream4 => dma2.dma_hifcr.write(|w| w.ctcif4().set_bit()),
@@ -729,8 +1283,8 @@ impl DmaStream {
             DmaStream::Dma1Stream6 => dma1.dma_hifcr.write(|w| w.chtif6().set_bit()),
             DmaStream::Dma1Stream7 => dma1.dma_hifcr.write(|w| w.chtif7().set_bit()),
 
-            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.chtif0().set_bit()),
-            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.chtif1().set_bit()),
+            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.chtif0().set_bit()),
+            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.chtif1().set_bit()),
             DmaStream::Dma2Stream2 => dma2.dma_lifcr.write(|w| w.chtif2().set_bit()),
             DmaStream::Dma2Stream3 => dma2.dma_lifcr.write(|w| w.chtif3().set_bit()),
             DmaStream::Dma2Stream4 => dma2.dma_hifcr.write(|w| w.chtif4().set_bit()),
@@ -754,8 +1308,8 @@ impl DmaStream {
             DmaStream::Dma1Stream6 => dma1.dma_hifcr.write(|w| w.cteif6().set_bit()),
             DmaStream::Dma1Stream7 => dma1.dma_hifcr.write(|w| w.cteif7().set_bit()),
 
-            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cteif0().set_bit()),
-            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cteif1().set_bit()),
+            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cteif0().set_bit()),
+            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cteif1().set_bit()),
             DmaStream::Dma2Stream2 => dma2.dma_lifcr.write(|w| w.cteif2().set_bit()),
             DmaStream::Dma2Stream3 => dma2.dma_lifcr.write(|w| w.cteif3().set_bit()),
             DmaStream::Dma2Stream4 => dma2.dma_hifcr.write(|w| w.cteif4().set_bit()),
@@ -779,8 +1333,8 @@ impl DmaStream {
             DmaStream::Dma1Stream6 => dma1.dma_hifcr.write(|w| w.cfeif6().set_bit()),
             DmaStream::Dma1Stream7 => dma1.dma_hifcr.write(|w| w.cfeif7().set_bit()),
 
-            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cfeif0().set_bit()),
-            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cfeif1().set_bit()),
+            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cfeif0().set_bit()),
+            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cfeif1().set_bit()),
             DmaStream::Dma2Stream2 => dma2.dma_lifcr.write(|w| w.cfeif2().set_bit()),
             DmaStream::Dma2Stream3 => dma2.dma_lifcr.write(|w| w.cfeif3().set_bit()),
             DmaStream::Dma2Stream4 => dma2.dma_hifcr.write(|w| w.cfeif4().set_bit()),
@@ -804,8 +1358,8 @@ impl DmaStream {
             DmaStream::Dma1Stream6 => dma1.dma_hifcr.write(|w| w.cdmeif6().set_bit()),
             DmaStream::Dma1Stream7 => dma1.dma_hifcr.write(|w| w.cdmeif7().set_bit()),
 
-            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cdmeif0().set_bit()),
-            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cdmeif1().set_bit()),
+            DmaStream::Dma2Stream0 => dma2.dma_lifcr.write(|w| w.cdmeif0().set_bit()),
+            DmaStream::Dma2Stream1 => dma2.dma_lifcr.write(|w| w.cdmeif1().set_bit()),
             DmaStream::Dma2Stream2 => dma2.dma_lifcr.write(|w| w.cdmeif2().set_bit()),
             DmaStream::Dma2Stream3 => dma2.dma_lifcr.write(|w| w.cdmeif3().set_bit()),
             DmaStream::Dma2Stream4 => dma2.dma_hifcr.write(|w| w.cdmeif4().set_bit()),
@@ -815,3 +1369,150 @@ impl DmaStream {
         }
     }
 }
+
+/// Returns a stream of a controller for a request input.
+///
+/// Via the DMAMUX, every request input can be connected to every stream, so there are no
+/// fixed assignments. The preferred stream is derived from the request number, which
+/// spreads the requests over the streams and puts the RX/TX requests of a peripheral on
+/// different streams. If the preferred stream is enabled, the next free stream is returned.
+/// When all streams of the controller are busy, the preferred stream is returned anyway.
+pub fn recommend_stream(request: DmaRequestInput, controller: DmaController) -> DmaStream {
+    let offset = match controller {
+        DmaController::Dma1 => 0,
+        DmaController::Dma2 => 8,
+    };
+    let preferred = u8::from(request) as usize % 8;
+
+    (0..8)
+        .map(|i| ALL_STREAMS[offset + (preferred + i) % 8])
+        .find(|stream| !stream.is_enabled())
+        .unwrap_or(ALL_STREAMS[offset + preferred])
+}
+
+/// Handles the interrupt of a stream by waking a task waiting in `wait_complete_async`
+/// or `wait_half_async`.
+///
+/// This function must be called from the interrupt handler of the stream.
+/// The interrupts enabled by the waiting functions are disabled again, the flags are
+/// evaluated by the waiting task.
+pub fn on_interrupt(stream: DmaStream) {
+    stream.modify_cr_bits(0, CR_WAIT_INTERRUPTS | CR_WAIT_HALF_INTERRUPTS);
+    stream.modify_fcr_bits(0, FCR_WAIT_INTERRUPTS);
+
+    let waker = critical_section::with(|cs| WAKERS.borrow_ref_mut(cs)[stream as usize].take());
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// Handle for a running transfer in circular mode.
+#[derive(Debug)]
+pub struct CircularTransfer {
+    /// Stream used for the transfer.
+    stream: DmaStream,
+    /// Length of the buffer in number of data items.
+    length: usize,
+}
+
+impl CircularTransfer {
+    /// Returns a handle for a circular transfer that was started on a stream.
+    pub fn new(stream: DmaStream, length: usize) -> Self {
+        Self { stream, length }
+    }
+
+    /// Returns the stream used for the transfer.
+    pub fn stream(&self) -> DmaStream {
+        self.stream
+    }
+
+    /// Returns the length of the buffer in number of data items.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the buffer index the DMA will write to or read from next.
+    pub fn write_index(&self) -> usize {
+        if self.length == 0 {
+            return 0;
+        }
+
+        let remaining = self.stream.remaining_transfers() as usize;
+        (self.length - remaining.min(self.length)) % self.length
+    }
+}
+
+/// Verifies the mapping of the flag functions to the status register bits on the hardware.
+///
+/// For each stream, a memory-to-memory transfer of two bytes is performed to set the
+/// transfer complete and half transfer flags, the latter after the first byte. It is then checked that the flag functions
+/// of the stream report them, that the clear functions clear them one by one and that
+/// no other stream reports any of these flags. The error flags can't be set by software,
+/// so their clear functions are only checked for not clearing other flags of the stream.
+///
+/// Returns the first stream with a mismatch as error.
+/// This function must only be called when no other DMA transfers are running and
+/// the streams have to be initialized again afterwards.
+pub fn verify_flag_mapping() -> Result<(), DmaStream> {
+    let source: [u8; 2] = [0xA5, 0x5A];
+    let mut destination: [u8; 2] = [0; 2];
+
+    for stream in ALL_STREAMS {
+        for other in ALL_STREAMS {
+            other.clear_all_flags();
+        }
+
+        let config = DmaStreamConfig {
+            transfer_direction: TransferDirection::MemoryToMemory,
+            memory_increment: true,
+            peripheral_increment: true,
+            fifo_mode: true,
+            ..Default::default()
+        };
+        stream.init(config).map_err(|_| stream)?;
+        stream.start_transfer(
+            &raw mut destination as u32,
+            &raw const source as u32,
+            source.len(),
+        );
+
+        let start_time = crate::time::Instant::now();
+        while stream.flags() & FLAG_TC == 0 {
+            if start_time.is_elapsed_millis(10) {
+                return Err(stream);
+            }
+        }
+        stream.disable();
+
+        if !stream.is_transfer_complete() || !stream.is_half_transfer() {
+            return Err(stream);
+        }
+
+        // Clearing the error flags must not affect any flag.
+        stream.clear_transfer_error();
+        stream.clear_fifo_error();
+        stream.clear_direct_mode_error();
+        if stream.flags() & (FLAG_TC | FLAG_HT) != FLAG_TC | FLAG_HT {
+            return Err(stream);
+        }
+
+        stream.clear_half_transfer();
+        if stream.flags() & (FLAG_TC | FLAG_HT) != FLAG_TC {
+            return Err(stream);
+        }
+
+        stream.clear_transfer_complete();
+        if stream.flags() & FLAG_TC != 0 {
+            return Err(stream);
+        }
+
+        // No other stream must have flags set by the transfer or the clear functions.
+        for other in ALL_STREAMS {
+            if other as u8 != stream as u8 && other.flags() & (FLAG_TC | FLAG_HT) != 0 {
+                return Err(stream);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,255 @@
+//! Extended interrupt and event controller.
+//!
+//! Only the configurable lines 0 - 15 connected to the GPIO pins are supported.
+
+use core::cell::RefCell;
+use core::task::Waker;
+
+use critical_section::Mutex;
+
+use crate::gpio::Port;
+use crate::pac;
+
+#[cfg(feature = "mpu-ca7")]
+use crate::irq::Irqn;
+
+/// Wakers of the tasks waiting for an edge on a line.
+static WAKERS: Mutex<RefCell<[Option<Waker>; 16]>> = Mutex::new(RefCell::new([const { None }; 16]));
+
+/// Trigger edge.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Edge {
+    /// Rising edge.
+    Rising,
+    /// Falling edge.
+    Falling,
+    /// Rising and falling edge.
+    Both,
+}
+
+/// Configuration of a line, saved with `line_config()`.
+#[derive(Debug, Clone, Copy)]
+pub struct LineConfig {
+    /// Port selection bits.
+    port: u32,
+    /// Rising edge trigger enable.
+    rising: bool,
+    /// Falling edge trigger enable.
+    falling: bool,
+    /// Interrupt unmasked for the current core.
+    interrupt: bool,
+}
+
+/// Selects the port that is connected to a line.
+/// - `line`: Line number 0 - 15, identical to the pin number.
+/// - `port`: Port to connect.
+pub fn select_port(line: u8, port: Port) {
+    let shift = (line % 4) * 8;
+    let mask = !(0xFF << shift);
+    let value = (port as u32) << shift;
+
+    unsafe {
+        let regs = &(*pac::EXTI::ptr());
+        match line / 4 {
+            0 => regs
+                .exti_exticr1
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            1 => regs
+                .exti_exticr2
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            2 => regs
+                .exti_exticr3
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            3 => regs
+                .exti_exticr4
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            _ => {}
+        }
+    }
+}
+
+/// Sets the trigger edge for a line.
+pub fn set_trigger_edge(line: u8, edge: Edge) {
+    let rising = matches!(edge, Edge::Rising | Edge::Both);
+    let falling = matches!(edge, Edge::Falling | Edge::Both);
+
+    unsafe {
+        let regs = &(*pac::EXTI::ptr());
+        regs.exti_rtsr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, rising)));
+        regs.exti_ftsr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, falling)));
+    }
+}
+
+/// Unmasks the interrupt of a line for the current core.
+pub fn enable_interrupt(line: u8) {
+    set_interrupt_mask(line, true);
+}
+
+/// Masks the interrupt of a line for the current core.
+pub fn disable_interrupt(line: u8) {
+    set_interrupt_mask(line, false);
+}
+
+/// Returns if an edge was detected on a line.
+pub fn is_pending(line: u8) -> bool {
+    let regs = unsafe { &(*pac::EXTI::ptr()) };
+    let pending = regs.exti_rpr1.read().bits() | regs.exti_fpr1.read().bits();
+    pending & (1 << line) != 0
+}
+
+/// Clears the pending flags of a line.
+pub fn clear_pending(line: u8) {
+    unsafe {
+        let regs = &(*pac::EXTI::ptr());
+        regs.exti_rpr1.write(|w| w.bits(1 << line));
+        regs.exti_fpr1.write(|w| w.bits(1 << line));
+    }
+}
+
+/// Returns the port selection, trigger edges and interrupt mask of a line.
+pub fn line_config(line: u8) -> LineConfig {
+    let regs = unsafe { &(*pac::EXTI::ptr()) };
+    let exticr = match line / 4 {
+        0 => regs.exti_exticr1.read().bits(),
+        1 => regs.exti_exticr2.read().bits(),
+        2 => regs.exti_exticr3.read().bits(),
+        _ => regs.exti_exticr4.read().bits(),
+    };
+
+    #[cfg(feature = "mpu-ca7")]
+    let imr = regs.exti_c1imr1.read().bits();
+
+    #[cfg(feature = "mcu-cm4")]
+    let imr = regs.exti_c2imr1.read().bits();
+
+    LineConfig {
+        port: (exticr >> ((line % 4) * 8)) & 0xFF,
+        rising: regs.exti_rtsr1.read().bits() & (1 << line) != 0,
+        falling: regs.exti_ftsr1.read().bits() & (1 << line) != 0,
+        interrupt: imr & (1 << line) != 0,
+    }
+}
+
+/// Restores a configuration of a line saved with `line_config()`.
+pub fn restore_line_config(line: u8, config: LineConfig) {
+    let shift = (line % 4) * 8;
+    let mask = !(0xFF << shift);
+    let value = config.port << shift;
+
+    unsafe {
+        let regs = &(*pac::EXTI::ptr());
+        match line / 4 {
+            0 => regs
+                .exti_exticr1
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            1 => regs
+                .exti_exticr2
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            2 => regs
+                .exti_exticr3
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+            _ => regs
+                .exti_exticr4
+                .modify(|r, w| w.bits((r.bits() & mask) | value)),
+        }
+        regs.exti_rtsr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, config.rising)));
+        regs.exti_ftsr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, config.falling)));
+    }
+
+    set_interrupt_mask(line, config.interrupt);
+}
+
+/// Registers the waker of a task waiting for an edge on a line.
+pub(crate) fn register_waker(line: u8, waker: &Waker) {
+    critical_section::with(|cs| {
+        WAKERS.borrow_ref_mut(cs)[line as usize] = Some(waker.clone());
+    });
+}
+
+/// Handles the interrupt of a line by waking a task waiting in
+/// `Pin::wait_for_edge_async()` or the `Wait` functions.
+///
+/// This function must be called from the interrupt handler of the line. The interrupt
+/// is masked again, the pending flag is evaluated by the waiting task.
+pub fn on_interrupt(line: u8) {
+    disable_interrupt(line);
+
+    let waker = critical_section::with(|cs| WAKERS.borrow_ref_mut(cs)[line as usize].take());
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// Returns the interrupt number of a line.
+#[cfg(feature = "mpu-ca7")]
+pub fn irqn(line: u8) -> Irqn {
+    match line {
+        0 => Irqn::EXTI0,
+        1 => Irqn::EXTI1,
+        2 => Irqn::EXTI2,
+        3 => Irqn::EXTI3,
+        4 => Irqn::EXTI4,
+        5 => Irqn::EXTI5,
+        6 => Irqn::EXTI6,
+        7 => Irqn::EXTI7,
+        8 => Irqn::EXTI8,
+        9 => Irqn::EXTI9,
+        10 => Irqn::EXTI10,
+        11 => Irqn::EXTI11,
+        12 => Irqn::EXTI12,
+        13 => Irqn::EXTI13,
+        14 => Irqn::EXTI14,
+        _ => Irqn::EXTI15,
+    }
+}
+
+/// Returns the NVIC interrupt of a line.
+#[cfg(feature = "mcu-cm4")]
+pub fn interrupt(line: u8) -> pac::Interrupt {
+    match line {
+        0 => pac::Interrupt::EXTI0,
+        1 => pac::Interrupt::EXTI1,
+        2 => pac::Interrupt::EXTI2,
+        3 => pac::Interrupt::EXTI3,
+        4 => pac::Interrupt::EXTI4,
+        5 => pac::Interrupt::EXTI5,
+        6 => pac::Interrupt::EXTI6,
+        7 => pac::Interrupt::EXTI7,
+        8 => pac::Interrupt::EXTI8,
+        9 => pac::Interrupt::EXTI9,
+        10 => pac::Interrupt::EXTI10,
+        11 => pac::Interrupt::EXTI11,
+        12 => pac::Interrupt::EXTI12,
+        13 => pac::Interrupt::EXTI13,
+        14 => pac::Interrupt::EXTI14,
+        _ => pac::Interrupt::EXTI15,
+    }
+}
+
+/// Sets the interrupt mask bit of a line in the register of the current core.
+fn set_interrupt_mask(line: u8, state: bool) {
+    unsafe {
+        let regs = &(*pac::EXTI::ptr());
+
+        #[cfg(feature = "mpu-ca7")]
+        regs.exti_c1imr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, state)));
+
+        #[cfg(feature = "mcu-cm4")]
+        regs.exti_c2imr1
+            .modify(|r, w| w.bits(set_bit(r.bits(), line, state)));
+    }
+}
+
+/// Returns a register value with a single bit set or cleared.
+fn set_bit(value: u32, bit: u8, state: bool) -> u32 {
+    if state {
+        value | (1 << bit)
+    } else {
+        value & !(1 << bit)
+    }
+}
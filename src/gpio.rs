@@ -1,9 +1,20 @@
 //! GPIO ports and pins.
 
+use core::fmt;
+use core::future::poll_fn;
+use core::ptr::{read_volatile, write_volatile};
+use core::task::Poll;
+use core::time::Duration;
+
 pub use embedded_hal::digital::{ErrorType, InputPin, OutputPin, PinState, StatefulOutputPin};
 
-use crate::bitworker::BitWorker;
+use crate::bitworker::{bitmask, BitWorker};
+use crate::exti::{self, Edge};
 use crate::pac;
+use crate::time;
+
+#[cfg(feature = "mpu-ca7")]
+use crate::irq::{self, Irqn};
 
 /// Pin mode.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -59,7 +70,10 @@ pub enum OutputSpeed {
     Medium,
     /// High speed.
     High,
-    /// Very high speed.  
+    /// Very high speed.
+    ///
+    /// The I/O compensation cell should be enabled via `enable_io_compensation()`
+    /// for signal integrity.
     VeryHigh,
 }
 
@@ -126,7 +140,468 @@ pub enum Port {
     Z,
 }
 
+/// Errors.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Error {
+    /// Lock key was not set after the lock sequence.
+    LockFailed,
+    /// Output speed requires the I/O compensation cell, which is not ready.
+    CompensationCell,
+    /// I/O compensation cell did not get ready in time.
+    CompensationTimeout,
+}
+
+/// Errors for invalid pin settings.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PinError {
+    /// Pin number is not in the range 0 - 15.
+    InvalidPin,
+    /// Alternate function is not in the range 0 - 15.
+    InvalidAlternateFunction,
+}
+
+/// Timeout in milliseconds for the I/O compensation cell to get ready.
+const COMPENSATION_TIMEOUT: u64 = 10;
+
+/// Runs the lock key sequence on the LCKR register of a port and returns the LCKK bit.
+///
+/// The same value must be written three times with LCKK set, cleared and set again,
+/// followed by two reads. Any other access to the register in between aborts the sequence.
+macro_rules! gpio_lock {
+    ($gpio: ident, $lckr: ident, $mask: expr) => {
+        unsafe {
+            let regs = &(*pac::$gpio::ptr());
+            let value = (regs.$lckr.read().bits() & 0xFFFF) | $mask as u32;
+            regs.$lckr.write(|w| w.bits(LCKR_LCKK | value));
+            regs.$lckr.write(|w| w.bits(value));
+            regs.$lckr.write(|w| w.bits(LCKR_LCKK | value));
+            regs.$lckr.read();
+            regs.$lckr.read().bits() & LCKR_LCKK != 0
+        }
+    };
+}
+
+/// Lock key bit in the LCKR register.
+const LCKR_LCKK: u32 = 1 << 16;
+
+/// Writes the configuration registers of a port for all pins in a mask.
+///
+/// Each register is read and written only once, MODER is written last.
+macro_rules! gpio_port_configure {
+    (
+        $gpio: ident, $otyper: ident, $ospeedr: ident, $pupdr: ident, $afrl: ident,
+        $afrh: ident, $moder: ident, $mask: ident, $mode: ident, $speed: ident,
+        $otype: ident, $pull: ident
+    ) => {
+        unsafe {
+            let regs = &(*pac::$gpio::ptr());
+            let pins = (0..16).filter(|pin| $mask & (1 << pin) != 0);
+            let af = match $mode {
+                PinMode::Alt(af) => af,
+                _ => 0,
+            };
+
+            regs.$otyper.modify(|r, w| {
+                w.bits(
+                    pins.clone()
+                        .fold(r.bits(), |value, pin| otyper(value, pin, $otype)),
+                )
+            });
+            regs.$ospeedr.modify(|r, w| {
+                w.bits(
+                    pins.clone()
+                        .fold(r.bits(), |value, pin| ospeedr(value, pin, $speed)),
+                )
+            });
+            regs.$pupdr.modify(|r, w| {
+                w.bits(
+                    pins.clone()
+                        .fold(r.bits(), |value, pin| pupdr(value, pin, $pull)),
+                )
+            });
+            regs.$afrl.modify(|r, w| {
+                w.bits(
+                    pins.clone()
+                        .filter(|pin| *pin < 8)
+                        .fold(r.bits(), |value, pin| afr(value, pin, af)),
+                )
+            });
+            regs.$afrh.modify(|r, w| {
+                w.bits(
+                    pins.clone()
+                        .filter(|pin| *pin >= 8)
+                        .fold(r.bits(), |value, pin| afr(value, pin - 8, af)),
+                )
+            });
+            regs.$moder
+                .modify(|r, w| w.bits(pins.fold(r.bits(), |value, pin| modr(value, pin, $mode))));
+        }
+    };
+}
+
+/// Reads the registers of a port into a snapshot.
+macro_rules! gpio_port_snapshot {
+    (
+        $gpio: ident, $moder: ident, $otyper: ident, $ospeedr: ident, $pupdr: ident,
+        $afrl: ident, $afrh: ident, $idr: ident, $odr: ident
+    ) => {
+        unsafe {
+            let regs = &(*pac::$gpio::ptr());
+            PortSnapshot {
+                moder: regs.$moder.read().bits(),
+                otyper: regs.$otyper.read().bits(),
+                ospeedr: regs.$ospeedr.read().bits(),
+                pupdr: regs.$pupdr.read().bits(),
+                afrl: regs.$afrl.read().bits(),
+                afrh: regs.$afrh.read().bits(),
+                idr: regs.$idr.read().bits(),
+                odr: regs.$odr.read().bits(),
+            }
+        }
+    };
+}
+
 impl Port {
+    /// Configures several pins of a port at once.
+    /// - `mask`: Pins to configure, bit 0 for pin 0 etc.
+    /// - `mode`: Pin mode, including the alternate function.
+    /// - `speed`: Output speed.
+    /// - `otype`: Output type.
+    /// - `pull`: Pull mode.
+    ///
+    /// Every configuration register is accessed only once for all pins. The mode is
+    /// written last, so the pins are switched with their complete configuration applied.
+    ///
+    /// Returns `PinError::InvalidAlternateFunction` for `PinMode::Alt` with a function
+    /// not in the range 0 - 15, the pins are not changed then.
+    pub fn configure_pins(
+        &mut self,
+        mask: u16,
+        mode: PinMode,
+        speed: OutputSpeed,
+        otype: OutputType,
+        pull: PullMode,
+    ) -> Result<(), PinError> {
+        if let PinMode::Alt(af) = mode {
+            if af > 15 {
+                return Err(PinError::InvalidAlternateFunction);
+            }
+        }
+
+        match self {
+            Port::A => gpio_port_configure!(
+                GPIOA,
+                gpioa_otyper,
+                gpioa_ospeedr,
+                gpioa_pupdr,
+                gpioa_afrl,
+                gpioa_afrh,
+                gpioa_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::B => gpio_port_configure!(
+                GPIOB,
+                gpiob_otyper,
+                gpiob_ospeedr,
+                gpiob_pupdr,
+                gpiob_afrl,
+                gpiob_afrh,
+                gpiob_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::C => gpio_port_configure!(
+                GPIOC,
+                gpioc_otyper,
+                gpioc_ospeedr,
+                gpioc_pupdr,
+                gpioc_afrl,
+                gpioc_afrh,
+                gpioc_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::D => gpio_port_configure!(
+                GPIOD,
+                gpiod_otyper,
+                gpiod_ospeedr,
+                gpiod_pupdr,
+                gpiod_afrl,
+                gpiod_afrh,
+                gpiod_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::E => gpio_port_configure!(
+                GPIOE,
+                gpioe_otyper,
+                gpioe_ospeedr,
+                gpioe_pupdr,
+                gpioe_afrl,
+                gpioe_afrh,
+                gpioe_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::F => gpio_port_configure!(
+                GPIOF,
+                gpiof_otyper,
+                gpiof_ospeedr,
+                gpiof_pupdr,
+                gpiof_afrl,
+                gpiof_afrh,
+                gpiof_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::G => gpio_port_configure!(
+                GPIOG,
+                gpiog_otyper,
+                gpiog_ospeedr,
+                gpiog_pupdr,
+                gpiog_afrl,
+                gpiog_afrh,
+                gpiog_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::H => gpio_port_configure!(
+                GPIOH,
+                gpioh_otyper,
+                gpioh_ospeedr,
+                gpioh_pupdr,
+                gpioh_afrl,
+                gpioh_afrh,
+                gpioh_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::I => gpio_port_configure!(
+                GPIOI,
+                gpioi_otyper,
+                gpioi_ospeedr,
+                gpioi_pupdr,
+                gpioi_afrl,
+                gpioi_afrh,
+                gpioi_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::J => gpio_port_configure!(
+                GPIOJ,
+                gpioj_otyper,
+                gpioj_ospeedr,
+                gpioj_pupdr,
+                gpioj_afrl,
+                gpioj_afrh,
+                gpioj_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::K => gpio_port_configure!(
+                GPIOK,
+                gpiok_otyper,
+                gpiok_ospeedr,
+                gpiok_pupdr,
+                gpiok_afrl,
+                gpiok_afrh,
+                gpiok_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+            Port::Z => gpio_port_configure!(
+                GPIOZ,
+                gpioz_otyper,
+                gpioz_ospeedr,
+                gpioz_pupdr,
+                gpioz_afrl,
+                gpioz_afrh,
+                gpioz_moder,
+                mask,
+                mode,
+                speed,
+                otype,
+                pull
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Returns a snapshot of the configuration and data registers for debugging.
+    pub fn dump(&self) -> PortSnapshot {
+        match self {
+            Port::A => gpio_port_snapshot!(
+                GPIOA,
+                gpioa_moder,
+                gpioa_otyper,
+                gpioa_ospeedr,
+                gpioa_pupdr,
+                gpioa_afrl,
+                gpioa_afrh,
+                gpioa_idr,
+                gpioa_odr
+            ),
+            Port::B => gpio_port_snapshot!(
+                GPIOB,
+                gpiob_moder,
+                gpiob_otyper,
+                gpiob_ospeedr,
+                gpiob_pupdr,
+                gpiob_afrl,
+                gpiob_afrh,
+                gpiob_idr,
+                gpiob_odr
+            ),
+            Port::C => gpio_port_snapshot!(
+                GPIOC,
+                gpioc_moder,
+                gpioc_otyper,
+                gpioc_ospeedr,
+                gpioc_pupdr,
+                gpioc_afrl,
+                gpioc_afrh,
+                gpioc_idr,
+                gpioc_odr
+            ),
+            Port::D => gpio_port_snapshot!(
+                GPIOD,
+                gpiod_moder,
+                gpiod_otyper,
+                gpiod_ospeedr,
+                gpiod_pupdr,
+                gpiod_afrl,
+                gpiod_afrh,
+                gpiod_idr,
+                gpiod_odr
+            ),
+            Port::E => gpio_port_snapshot!(
+                GPIOE,
+                gpioe_moder,
+                gpioe_otyper,
+                gpioe_ospeedr,
+                gpioe_pupdr,
+                gpioe_afrl,
+                gpioe_afrh,
+                gpioe_idr,
+                gpioe_odr
+            ),
+            Port::F => gpio_port_snapshot!(
+                GPIOF,
+                gpiof_moder,
+                gpiof_otyper,
+                gpiof_ospeedr,
+                gpiof_pupdr,
+                gpiof_afrl,
+                gpiof_afrh,
+                gpiof_idr,
+                gpiof_odr
+            ),
+            Port::G => gpio_port_snapshot!(
+                GPIOG,
+                gpiog_moder,
+                gpiog_otyper,
+                gpiog_ospeedr,
+                gpiog_pupdr,
+                gpiog_afrl,
+                gpiog_afrh,
+                gpiog_idr,
+                gpiog_odr
+            ),
+            Port::H => gpio_port_snapshot!(
+                GPIOH,
+                gpioh_moder,
+                gpioh_otyper,
+                gpioh_ospeedr,
+                gpioh_pupdr,
+                gpioh_afrl,
+                gpioh_afrh,
+                gpioh_idr,
+                gpioh_odr
+            ),
+            Port::I => gpio_port_snapshot!(
+                GPIOI,
+                gpioi_moder,
+                gpioi_otyper,
+                gpioi_ospeedr,
+                gpioi_pupdr,
+                gpioi_afrl,
+                gpioi_afrh,
+                gpioi_idr,
+                gpioi_odr
+            ),
+            Port::J => gpio_port_snapshot!(
+                GPIOJ,
+                gpioj_moder,
+                gpioj_otyper,
+                gpioj_ospeedr,
+                gpioj_pupdr,
+                gpioj_afrl,
+                gpioj_afrh,
+                gpioj_idr,
+                gpioj_odr
+            ),
+            Port::K => gpio_port_snapshot!(
+                GPIOK,
+                gpiok_moder,
+                gpiok_otyper,
+                gpiok_ospeedr,
+                gpiok_pupdr,
+                gpiok_afrl,
+                gpiok_afrh,
+                gpiok_idr,
+                gpiok_odr
+            ),
+            Port::Z => gpio_port_snapshot!(
+                GPIOZ,
+                gpioz_moder,
+                gpioz_otyper,
+                gpioz_ospeedr,
+                gpioz_pupdr,
+                gpioz_afrl,
+                gpioz_afrh,
+                gpioz_idr,
+                gpioz_odr
+            ),
+        }
+    }
+
     /// Sets a range of pins on a port simultaneously.
     /// - `start_pin`: First pin in the range.
     /// - `pin_count`: Total number of pins.
@@ -153,6 +628,89 @@ impl Port {
         }
     }
 
+    /// Writes a value to a range of pins on a port with a single BSRR access.
+    /// - `start_pin`: First pin in the range.
+    /// - `pin_count`: Total number of pins.
+    /// - `value`: Value to write, bit 0 is written to `start_pin`.
+    ///
+    /// Pins in the range are set or reset according to the value, all other pins
+    /// of the port are left untouched.
+    pub fn set_bus_masked(&mut self, start_pin: u8, pin_count: u8, value: impl Into<u32>) {
+        self.write_bsrr(masked_bsrr(start_pin, pin_count, value.into()));
+    }
+
+    /// Toggles several pins on a port with a single BSRR write.
+    /// - `mask`: Pins to toggle, bit 0 for pin 0 etc.
+    ///
+    /// The current output levels are read from ODR, pins that are high are reset and
+    /// pins that are low are set. Other pins of the port are not affected.
+    pub fn toggle_pins(&mut self, mask: u16) {
+        unsafe {
+            match self {
+                Port::A => {
+                    let regs = &(*pac::GPIOA::ptr());
+                    let value = toggle_bsrr(regs.gpioa_odr.read().bits(), mask);
+                    regs.gpioa_bsrr.write(|w| w.bits(value));
+                }
+                Port::B => {
+                    let regs = &(*pac::GPIOB::ptr());
+                    let value = toggle_bsrr(regs.gpiob_odr.read().bits(), mask);
+                    regs.gpiob_bsrr.write(|w| w.bits(value));
+                }
+                Port::C => {
+                    let regs = &(*pac::GPIOC::ptr());
+                    let value = toggle_bsrr(regs.gpioc_odr.read().bits(), mask);
+                    regs.gpioc_bsrr.write(|w| w.bits(value));
+                }
+                Port::D => {
+                    let regs = &(*pac::GPIOD::ptr());
+                    let value = toggle_bsrr(regs.gpiod_odr.read().bits(), mask);
+                    regs.gpiod_bsrr.write(|w| w.bits(value));
+                }
+                Port::E => {
+                    let regs = &(*pac::GPIOE::ptr());
+                    let value = toggle_bsrr(regs.gpioe_odr.read().bits(), mask);
+                    regs.gpioe_bsrr.write(|w| w.bits(value));
+                }
+                Port::F => {
+                    let regs = &(*pac::GPIOF::ptr());
+                    let value = toggle_bsrr(regs.gpiof_odr.read().bits(), mask);
+                    regs.gpiof_bsrr.write(|w| w.bits(value));
+                }
+                Port::G => {
+                    let regs = &(*pac::GPIOG::ptr());
+                    let value = toggle_bsrr(regs.gpiog_odr.read().bits(), mask);
+                    regs.gpiog_bsrr.write(|w| w.bits(value));
+                }
+                Port::H => {
+                    let regs = &(*pac::GPIOH::ptr());
+                    let value = toggle_bsrr(regs.gpioh_odr.read().bits(), mask);
+                    regs.gpioh_bsrr.write(|w| w.bits(value));
+                }
+                Port::I => {
+                    let regs = &(*pac::GPIOI::ptr());
+                    let value = toggle_bsrr(regs.gpioi_odr.read().bits(), mask);
+                    regs.gpioi_bsrr.write(|w| w.bits(value));
+                }
+                Port::J => {
+                    let regs = &(*pac::GPIOJ::ptr());
+                    let value = toggle_bsrr(regs.gpioj_odr.read().bits(), mask);
+                    regs.gpioj_bsrr.write(|w| w.bits(value));
+                }
+                Port::K => {
+                    let regs = &(*pac::GPIOK::ptr());
+                    let value = toggle_bsrr(regs.gpiok_odr.read().bits(), mask);
+                    regs.gpiok_bsrr.write(|w| w.bits(value));
+                }
+                Port::Z => {
+                    let regs = &(*pac::GPIOZ::ptr());
+                    let value = toggle_bsrr(regs.gpioz_odr.read().bits(), mask);
+                    regs.gpioz_bsrr.write(|w| w.bits(value));
+                }
+            }
+        }
+    }
+
     /// Reads a range of pins on a port simultaneously.
     /// - `start_pin`: First pin in the range.
     /// - `pin_count`: Total number of pins.
@@ -175,6 +733,171 @@ impl Port {
         };
         BitWorker::new(value).subvalue(start_pin, pin_count)
     }
+
+    /// Locks the configuration of several pins until the next reset.
+    /// - `mask`: Pins to lock, bit 0 for pin 0 etc.
+    ///
+    /// Mode, output type, speed, pull mode and alternate function can't be changed anymore
+    /// once locked. The lock register is frozen after the first lock sequence, so all pins
+    /// of a port that need to be locked must be passed in a single call. Returns
+    /// `Error::LockFailed` if the lock key was not set or a pin of the mask is not locked.
+    pub fn lock_pins(&mut self, mask: u16) -> Result<(), Error> {
+        let locked = match self {
+            Port::A => gpio_lock!(GPIOA, gpioa_lckr, mask),
+            Port::B => gpio_lock!(GPIOB, gpiob_lckr, mask),
+            Port::C => gpio_lock!(GPIOC, gpioc_lckr, mask),
+            Port::D => gpio_lock!(GPIOD, gpiod_lckr, mask),
+            Port::E => gpio_lock!(GPIOE, gpioe_lckr, mask),
+            Port::F => gpio_lock!(GPIOF, gpiof_lckr, mask),
+            Port::G => gpio_lock!(GPIOG, gpiog_lckr, mask),
+            Port::H => gpio_lock!(GPIOH, gpioh_lckr, mask),
+            Port::I => gpio_lock!(GPIOI, gpioi_lckr, mask),
+            Port::J => gpio_lock!(GPIOJ, gpioj_lckr, mask),
+            Port::K => gpio_lock!(GPIOK, gpiok_lckr, mask),
+            Port::Z => gpio_lock!(GPIOZ, gpioz_lckr, mask),
+        };
+
+        if locked && self.read_lckr() & mask as u32 == mask as u32 {
+            Ok(())
+        } else {
+            Err(Error::LockFailed)
+        }
+    }
+
+    /// Returns the lock register value.
+    fn read_lckr(&self) -> u32 {
+        unsafe {
+            match self {
+                Port::A => (*pac::GPIOA::ptr()).gpioa_lckr.read().bits(),
+                Port::B => (*pac::GPIOB::ptr()).gpiob_lckr.read().bits(),
+                Port::C => (*pac::GPIOC::ptr()).gpioc_lckr.read().bits(),
+                Port::D => (*pac::GPIOD::ptr()).gpiod_lckr.read().bits(),
+                Port::E => (*pac::GPIOE::ptr()).gpioe_lckr.read().bits(),
+                Port::F => (*pac::GPIOF::ptr()).gpiof_lckr.read().bits(),
+                Port::G => (*pac::GPIOG::ptr()).gpiog_lckr.read().bits(),
+                Port::H => (*pac::GPIOH::ptr()).gpioh_lckr.read().bits(),
+                Port::I => (*pac::GPIOI::ptr()).gpioi_lckr.read().bits(),
+                Port::J => (*pac::GPIOJ::ptr()).gpioj_lckr.read().bits(),
+                Port::K => (*pac::GPIOK::ptr()).gpiok_lckr.read().bits(),
+                Port::Z => (*pac::GPIOZ::ptr()).gpioz_lckr.read().bits(),
+            }
+        }
+    }
+
+    /// Returns the output data register value.
+    fn read_odr(&self) -> u32 {
+        unsafe {
+            match self {
+                Port::A => (*pac::GPIOA::ptr()).gpioa_odr.read().bits(),
+                Port::B => (*pac::GPIOB::ptr()).gpiob_odr.read().bits(),
+                Port::C => (*pac::GPIOC::ptr()).gpioc_odr.read().bits(),
+                Port::D => (*pac::GPIOD::ptr()).gpiod_odr.read().bits(),
+                Port::E => (*pac::GPIOE::ptr()).gpioe_odr.read().bits(),
+                Port::F => (*pac::GPIOF::ptr()).gpiof_odr.read().bits(),
+                Port::G => (*pac::GPIOG::ptr()).gpiog_odr.read().bits(),
+                Port::H => (*pac::GPIOH::ptr()).gpioh_odr.read().bits(),
+                Port::I => (*pac::GPIOI::ptr()).gpioi_odr.read().bits(),
+                Port::J => (*pac::GPIOJ::ptr()).gpioj_odr.read().bits(),
+                Port::K => (*pac::GPIOK::ptr()).gpiok_odr.read().bits(),
+                Port::Z => (*pac::GPIOZ::ptr()).gpioz_odr.read().bits(),
+            }
+        }
+    }
+
+    /// Writes a value to the bit set/reset register.
+    fn write_bsrr(&self, value: u32) {
+        unsafe {
+            match self {
+                Port::A => (*pac::GPIOA::ptr()).gpioa_bsrr.write(|w| w.bits(value)),
+                Port::B => (*pac::GPIOB::ptr()).gpiob_bsrr.write(|w| w.bits(value)),
+                Port::C => (*pac::GPIOC::ptr()).gpioc_bsrr.write(|w| w.bits(value)),
+                Port::D => (*pac::GPIOD::ptr()).gpiod_bsrr.write(|w| w.bits(value)),
+                Port::E => (*pac::GPIOE::ptr()).gpioe_bsrr.write(|w| w.bits(value)),
+                Port::F => (*pac::GPIOF::ptr()).gpiof_bsrr.write(|w| w.bits(value)),
+                Port::G => (*pac::GPIOG::ptr()).gpiog_bsrr.write(|w| w.bits(value)),
+                Port::H => (*pac::GPIOH::ptr()).gpioh_bsrr.write(|w| w.bits(value)),
+                Port::I => (*pac::GPIOI::ptr()).gpioi_bsrr.write(|w| w.bits(value)),
+                Port::J => (*pac::GPIOJ::ptr()).gpioj_bsrr.write(|w| w.bits(value)),
+                Port::K => (*pac::GPIOK::ptr()).gpiok_bsrr.write(|w| w.bits(value)),
+                Port::Z => (*pac::GPIOZ::ptr()).gpioz_bsrr.write(|w| w.bits(value)),
+            }
+        }
+    }
+}
+
+/// Register values of a port captured by `Port::dump()`.
+///
+/// The `Debug` output lists the raw register values followed by the decoded
+/// mode, alternate function and levels of each pin.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PortSnapshot {
+    /// Mode register.
+    pub moder: u32,
+    /// Output type register.
+    pub otyper: u32,
+    /// Output speed register.
+    pub ospeedr: u32,
+    /// Pull-up/pull-down register.
+    pub pupdr: u32,
+    /// Alternate function register for pins 0 - 7.
+    pub afrl: u32,
+    /// Alternate function register for pins 8 - 15.
+    pub afrh: u32,
+    /// Input data register.
+    pub idr: u32,
+    /// Output data register.
+    pub odr: u32,
+}
+
+impl fmt::Debug for PortSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "MODER={:#010x} OTYPER={:#010x} OSPEEDR={:#010x} PUPDR={:#010x}",
+            self.moder, self.otyper, self.ospeedr, self.pupdr
+        )?;
+        writeln!(
+            f,
+            "AFRL={:#010x} AFRH={:#010x} IDR={:#010x} ODR={:#010x}",
+            self.afrl, self.afrh, self.idr, self.odr
+        )?;
+
+        for pin in 0..16 {
+            let afr = if pin < 8 { self.afrl } else { self.afrh };
+            let af = BitWorker::new(afr).subvalue((pin % 8) * 4, 4);
+            let mode = match BitWorker::new(self.moder).subvalue(pin * 2, 2) {
+                0b00 => "Input",
+                0b01 => "Output",
+                0b10 => "Alt",
+                _ => "Analog",
+            };
+            let pull = match BitWorker::new(self.pupdr).subvalue(pin * 2, 2) {
+                0b01 => "PullUp",
+                0b10 => "PullDown",
+                _ => "Floating",
+            };
+            let otype = if BitWorker::new(self.otyper).is_set(pin) {
+                "OpenDrain"
+            } else {
+                "PushPull"
+            };
+
+            writeln!(
+                f,
+                "{:2}: {:6} AF{:<2} {:9} {:8} speed={} in={} out={}",
+                pin,
+                mode,
+                af,
+                otype,
+                pull,
+                BitWorker::new(self.ospeedr).subvalue(pin * 2, 2),
+                BitWorker::new(self.idr).subvalue(pin, 1),
+                BitWorker::new(self.odr).subvalue(pin, 1),
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Bus covering several pins.
@@ -208,6 +931,42 @@ impl Bus {
     pub fn get_input(&mut self) -> u32 {
         self.port.get_bus_input(self.start_pin, self.pin_count)
     }
+
+    /// Waits until the masked input value matches the expected value.
+    /// - `expected`: Expected value, bits outside the mask are ignored.
+    /// - `mask`: Bits of the input value to compare.
+    pub fn wait_for_value(&self, expected: u32, mask: u32) {
+        while !self.is_value(expected, mask) {}
+    }
+
+    /// Waits asynchronuously until the masked input value matches the expected value.
+    pub async fn wait_for_value_async(&self, expected: u32, mask: u32) {
+        poll_fn(|cx| {
+            if self.is_value(expected, mask) {
+                Poll::Ready(())
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Returns if the masked input value matches the expected value.
+    fn is_value(&self, expected: u32, mask: u32) -> bool {
+        self.port.get_bus_input(self.start_pin, self.pin_count) & mask == expected & mask
+    }
+}
+
+/// Returns a pin from its name in the datasheet, checked at compile time.
+///
+/// Example: `let led = pin!(PG11);`
+#[macro_export]
+macro_rules! pin {
+    ($name: ident) => {{
+        const PIN: $crate::gpio::Pin = $crate::gpio::Pin::from_name(stringify!($name));
+        PIN
+    }};
 }
 
 /// Pin.
@@ -219,22 +978,382 @@ pub struct Pin {
     pub pin: u8,
 }
 
+/// Output configuration of a pin saved by `Pin::set_high_impedance()`.
+#[must_use]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SavedOutput {
+    /// Mode including the alternate function.
+    mode: PinMode,
+    /// Output type.
+    output_type: OutputType,
+}
+
 impl Pin {
     /// Returns a pin.
+    ///
+    /// The pin number is not checked, see `try_new()`.
     pub fn new(port: Port, pin: u8) -> Self {
         Self { port, pin }
     }
 
+    /// Returns a pin after checking the pin number.
+    ///
+    /// Returns `PinError::InvalidPin` if `pin` is not in the range 0 - 15.
+    pub fn try_new(port: Port, pin: u8) -> Result<Self, PinError> {
+        if pin > 15 {
+            return Err(PinError::InvalidPin);
+        }
+
+        Ok(Self { port, pin })
+    }
+
+    /// Returns a pin from its name in the datasheet, e.g. `"PG11"`.
+    ///
+    /// Panics if the name is invalid, which is a compile time error in a const context.
+    /// See also the `pin!` macro.
+    pub const fn from_name(name: &str) -> Self {
+        let bytes = name.as_bytes();
+
+        if bytes.len() < 3 || bytes.len() > 4 || bytes[0] != b'P' {
+            panic!("Invalid pin name.");
+        }
+
+        let port = match bytes[1] {
+            b'A' => Port::A,
+            b'B' => Port::B,
+            b'C' => Port::C,
+            b'D' => Port::D,
+            b'E' => Port::E,
+            b'F' => Port::F,
+            b'G' => Port::G,
+            b'H' => Port::H,
+            b'I' => Port::I,
+            b'J' => Port::J,
+            b'K' => Port::K,
+            b'Z' => Port::Z,
+            _ => panic!("Invalid port letter."),
+        };
+
+        let mut pin = 0;
+        let mut i = 2;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                panic!("Invalid pin number.");
+            }
+            pin = pin * 10 + (bytes[i] - b'0');
+            i += 1;
+        }
+
+        if pin > 15 || (bytes.len() == 4 && bytes[2] == b'0') {
+            panic!("Invalid pin number.");
+        }
+
+        Self { port, pin }
+    }
+
     /// Returns a pin initialized in the desired mode.
+    ///
+    /// Panics for `PinMode::Alt` with a function not in the range 0 - 15.
     pub fn with_mode(port: Port, pin: u8, mode: PinMode) -> Self {
         let mut pin = Self { port, pin };
-        pin.set_mode(mode);
+        if pin.set_mode(mode).is_err() {
+            panic!("Invalid alternate function.");
+        }
 
         pin
     }
 
-    /// Returns a pin initialized in the desired mode.
-    pub fn set_mode(&mut self, mode: PinMode) {
+    /// Sets the mode.
+    ///
+    /// In analog mode, the pull resistors are disabled. Connecting the pin to an ADC
+    /// channel is not done here and must be configured in the ADC.
+    ///
+    /// Returns `PinError::InvalidAlternateFunction` for `PinMode::Alt` with a function
+    /// not in the range 0 - 15, the pin is not changed then.
+    pub fn set_mode(&mut self, mode: PinMode) -> Result<(), PinError> {
+        if let PinMode::Alt(af) = mode {
+            if af > 15 {
+                return Err(PinError::InvalidAlternateFunction);
+            }
+        }
+
+        self.apply_mode(mode);
+
+        Ok(())
+    }
+
+    /// Sets a mode that is known to be valid.
+    pub(crate) fn apply_mode(&mut self, mode: PinMode) {
+        self.set_mode_bits(mode);
+
+        match mode {
+            PinMode::Alt(af_mode) => self.write_alternate_function(af_mode & 0xF),
+            _ => self.write_alternate_function(0),
+        }
+
+        // Pull resistors would load the analog input.
+        if mode == PinMode::Analog {
+            self.set_pull_mode(PullMode::Floating);
+        }
+    }
+
+    /// Returns the currently configured mode, including the alternate function.
+    pub fn mode(&self) -> PinMode {
+        let moder = match self.port {
+            Port::A => unsafe { (*pac::GPIOA::ptr()).gpioa_moder.read().bits() },
+            Port::B => unsafe { (*pac::GPIOB::ptr()).gpiob_moder.read().bits() },
+            Port::C => unsafe { (*pac::GPIOC::ptr()).gpioc_moder.read().bits() },
+            Port::D => unsafe { (*pac::GPIOD::ptr()).gpiod_moder.read().bits() },
+            Port::E => unsafe { (*pac::GPIOE::ptr()).gpioe_moder.read().bits() },
+            Port::F => unsafe { (*pac::GPIOF::ptr()).gpiof_moder.read().bits() },
+            Port::G => unsafe { (*pac::GPIOG::ptr()).gpiog_moder.read().bits() },
+            Port::H => unsafe { (*pac::GPIOH::ptr()).gpioh_moder.read().bits() },
+            Port::I => unsafe { (*pac::GPIOI::ptr()).gpioi_moder.read().bits() },
+            Port::J => unsafe { (*pac::GPIOJ::ptr()).gpioj_moder.read().bits() },
+            Port::K => unsafe { (*pac::GPIOK::ptr()).gpiok_moder.read().bits() },
+            Port::Z => unsafe { (*pac::GPIOZ::ptr()).gpioz_moder.read().bits() },
+        };
+
+        match BitWorker::new(moder).subvalue(self.pin * 2, 2) {
+            0b00 => PinMode::Input,
+            0b01 => PinMode::Output,
+            0b10 => PinMode::Alt(self.alternate_function()),
+            _ => PinMode::Analog,
+        }
+    }
+
+    /// Returns the currently configured alternate function number 0 - 15.
+    ///
+    /// The value is also returned if the pin is not in alternate function mode.
+    pub fn alternate_function(&self) -> u8 {
+        let afr = match self.port {
+            Port::A => unsafe {
+                let regs = &(*pac::GPIOA::ptr());
+                if self.pin < 8 {
+                    regs.gpioa_afrl.read().bits()
+                } else {
+                    regs.gpioa_afrh.read().bits()
+                }
+            },
+            Port::B => unsafe {
+                let regs = &(*pac::GPIOB::ptr());
+                if self.pin < 8 {
+                    regs.gpiob_afrl.read().bits()
+                } else {
+                    regs.gpiob_afrh.read().bits()
+                }
+            },
+            Port::C => unsafe {
+                let regs = &(*pac::GPIOC::ptr());
+                if self.pin < 8 {
+                    regs.gpioc_afrl.read().bits()
+                } else {
+                    regs.gpioc_afrh.read().bits()
+                }
+            },
+            Port::D => unsafe {
+                let regs = &(*pac::GPIOD::ptr());
+                if self.pin < 8 {
+                    regs.gpiod_afrl.read().bits()
+                } else {
+                    regs.gpiod_afrh.read().bits()
+                }
+            },
+            Port::E => unsafe {
+                let regs = &(*pac::GPIOE::ptr());
+                if self.pin < 8 {
+                    regs.gpioe_afrl.read().bits()
+                } else {
+                    regs.gpioe_afrh.read().bits()
+                }
+            },
+            Port::F => unsafe {
+                let regs = &(*pac::GPIOF::ptr());
+                if self.pin < 8 {
+                    regs.gpiof_afrl.read().bits()
+                } else {
+                    regs.gpiof_afrh.read().bits()
+                }
+            },
+            Port::G => unsafe {
+                let regs = &(*pac::GPIOG::ptr());
+                if self.pin < 8 {
+                    regs.gpiog_afrl.read().bits()
+                } else {
+                    regs.gpiog_afrh.read().bits()
+                }
+            },
+            Port::H => unsafe {
+                let regs = &(*pac::GPIOH::ptr());
+                if self.pin < 8 {
+                    regs.gpioh_afrl.read().bits()
+                } else {
+                    regs.gpioh_afrh.read().bits()
+                }
+            },
+            Port::I => unsafe {
+                let regs = &(*pac::GPIOI::ptr());
+                if self.pin < 8 {
+                    regs.gpioi_afrl.read().bits()
+                } else {
+                    regs.gpioi_afrh.read().bits()
+                }
+            },
+            Port::J => unsafe {
+                let regs = &(*pac::GPIOJ::ptr());
+                if self.pin < 8 {
+                    regs.gpioj_afrl.read().bits()
+                } else {
+                    regs.gpioj_afrh.read().bits()
+                }
+            },
+            Port::K => unsafe {
+                let regs = &(*pac::GPIOK::ptr());
+                if self.pin < 8 {
+                    regs.gpiok_afrl.read().bits()
+                } else {
+                    regs.gpiok_afrh.read().bits()
+                }
+            },
+            Port::Z => unsafe {
+                let regs = &(*pac::GPIOZ::ptr());
+                if self.pin < 8 {
+                    regs.gpioz_afrl.read().bits()
+                } else {
+                    regs.gpioz_afrh.read().bits()
+                }
+            },
+        };
+
+        BitWorker::new(afr).subvalue((self.pin % 8) * 4, 4) as u8
+    }
+
+    /// Converts the pin into a `FastPin` with resolved register addresses.
+    pub fn into_fast(self) -> FastPin {
+        let (bsrr, idr) = match self.port {
+            Port::A => unsafe {
+                let regs = &(*pac::GPIOA::ptr());
+                (
+                    regs.gpioa_bsrr.as_ptr(),
+                    regs.gpioa_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::B => unsafe {
+                let regs = &(*pac::GPIOB::ptr());
+                (
+                    regs.gpiob_bsrr.as_ptr(),
+                    regs.gpiob_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::C => unsafe {
+                let regs = &(*pac::GPIOC::ptr());
+                (
+                    regs.gpioc_bsrr.as_ptr(),
+                    regs.gpioc_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::D => unsafe {
+                let regs = &(*pac::GPIOD::ptr());
+                (
+                    regs.gpiod_bsrr.as_ptr(),
+                    regs.gpiod_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::E => unsafe {
+                let regs = &(*pac::GPIOE::ptr());
+                (
+                    regs.gpioe_bsrr.as_ptr(),
+                    regs.gpioe_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::F => unsafe {
+                let regs = &(*pac::GPIOF::ptr());
+                (
+                    regs.gpiof_bsrr.as_ptr(),
+                    regs.gpiof_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::G => unsafe {
+                let regs = &(*pac::GPIOG::ptr());
+                (
+                    regs.gpiog_bsrr.as_ptr(),
+                    regs.gpiog_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::H => unsafe {
+                let regs = &(*pac::GPIOH::ptr());
+                (
+                    regs.gpioh_bsrr.as_ptr(),
+                    regs.gpioh_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::I => unsafe {
+                let regs = &(*pac::GPIOI::ptr());
+                (
+                    regs.gpioi_bsrr.as_ptr(),
+                    regs.gpioi_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::J => unsafe {
+                let regs = &(*pac::GPIOJ::ptr());
+                (
+                    regs.gpioj_bsrr.as_ptr(),
+                    regs.gpioj_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::K => unsafe {
+                let regs = &(*pac::GPIOK::ptr());
+                (
+                    regs.gpiok_bsrr.as_ptr(),
+                    regs.gpiok_idr.as_ptr() as *const u32,
+                )
+            },
+            Port::Z => unsafe {
+                let regs = &(*pac::GPIOZ::ptr());
+                (
+                    regs.gpioz_bsrr.as_ptr(),
+                    regs.gpioz_idr.as_ptr() as *const u32,
+                )
+            },
+        };
+
+        FastPin {
+            pin: self,
+            bsrr,
+            idr,
+        }
+    }
+
+    /// Releases the line by switching the pin to input mode.
+    ///
+    /// Returns the current mode and output type to be passed to `restore_output()`.
+    /// Only the mode is changed, output type, speed, pull mode and the output data are
+    /// kept, so `restore_output()` drives the previous level again. Together with an
+    /// external pull-up, this is used for open-drain style protocols like 1-Wire.
+    pub fn set_high_impedance(&mut self) -> SavedOutput {
+        let output_type = if BitWorker::new(self.port.dump().otyper).is_set(self.pin) {
+            OutputType::OpenDrain
+        } else {
+            OutputType::PushPull
+        };
+        let saved = SavedOutput {
+            mode: self.mode(),
+            output_type,
+        };
+
+        self.set_mode_bits(PinMode::Input);
+
+        saved
+    }
+
+    /// Restores the mode and output type returned by `set_high_impedance()`.
+    pub fn restore_output(&mut self, saved: SavedOutput) {
+        self.set_output_type(saved.output_type);
+        self.apply_mode(saved.mode);
+    }
+
+    /// Writes the MODER bits only, leaving the alternate function and pull mode untouched.
+    fn set_mode_bits(&mut self, mode: PinMode) {
         match self.port {
             Port::A => unsafe {
                 let regs = &(*pac::GPIOA::ptr());
@@ -297,11 +1416,16 @@ impl Pin {
                     .modify(|r, w| w.bits(modr(r.bits(), self.pin, mode)));
             },
         }
+    }
 
-        match mode {
-            PinMode::Alt(af_mode) => self.set_alternate_function(af_mode),
-            _ => self.set_alternate_function(0),
-        }
+    /// Switches the pin to output mode, driving the level that is currently read on it.
+    ///
+    /// The output data register is written before changing the mode, so the pin never
+    /// drives the previous output level, e.g. when taking over a bus line.
+    pub fn into_output_preserving_level(&mut self) {
+        let state = self.get_input_state();
+        self.set_output_state(state);
+        self.apply_mode(PinMode::Output);
     }
 
     /// Returns the input state.
@@ -358,73 +1482,46 @@ impl Pin {
         }
     }
 
+    /// Returns the output state that is driven, read from the output data register.
+    ///
+    /// Unlike `get_input_state()`, this is not affected by the actual level on the pin,
+    /// e.g. for open-drain outputs pulled low externally.
+    pub fn get_output_state(&self) -> PinState {
+        odr(self.port.read_odr(), self.pin)
+    }
+
+    /// Inverts the output state.
+    ///
+    /// The driven level is read from the output data register and the inverted level is
+    /// written with a single BSRR access. Other pins of the port are never written,
+    /// so toggling different pins of the same port from both cores is safe.
+    pub fn toggle_output_state(&mut self) {
+        self.port
+            .write_bsrr(toggle_pin_bsrr(self.port.read_odr(), self.pin));
+    }
+
     /// Sets the output state.
     pub fn set_output_state(&mut self, state: impl Into<PinState>) {
-        match self.port {
-            Port::A => unsafe {
-                let regs = &(*pac::GPIOA::ptr());
-                regs.gpioa_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::B => unsafe {
-                let regs = &(*pac::GPIOB::ptr());
-                regs.gpiob_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::C => unsafe {
-                let regs = &(*pac::GPIOC::ptr());
-                regs.gpioc_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::D => unsafe {
-                let regs = &(*pac::GPIOD::ptr());
-                regs.gpiod_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::E => unsafe {
-                let regs = &(*pac::GPIOE::ptr());
-                regs.gpioe_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::F => unsafe {
-                let regs = &(*pac::GPIOF::ptr());
-                regs.gpiof_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::G => unsafe {
-                let regs = &(*pac::GPIOG::ptr());
-                regs.gpiog_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::H => unsafe {
-                let regs = &(*pac::GPIOH::ptr());
-                regs.gpioh_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::I => unsafe {
-                let regs = &(*pac::GPIOI::ptr());
-                regs.gpioi_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::J => unsafe {
-                let regs = &(*pac::GPIOJ::ptr());
-                regs.gpioj_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::K => unsafe {
-                let regs = &(*pac::GPIOK::ptr());
-                regs.gpiok_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
-            Port::Z => unsafe {
-                let regs = &(*pac::GPIOZ::ptr());
-                regs.gpioz_bsrr
-                    .write(|w| w.bits(bsrr(self.pin, state.into())))
-            },
+        self.port.write_bsrr(bsrr(self.pin, state.into()));
+    }
+
+    /// Sets the output speed after checking the requirements.
+    ///
+    /// Returns `Error::CompensationCell` for `OutputSpeed::VeryHigh` if the I/O
+    /// compensation cell was not enabled before via `enable_io_compensation()`.
+    pub fn try_set_output_speed(&mut self, output_speed: OutputSpeed) -> Result<(), Error> {
+        if output_speed == OutputSpeed::VeryHigh && !is_io_compensation_ready() {
+            return Err(Error::CompensationCell);
         }
+
+        self.set_output_speed(output_speed);
+
+        Ok(())
     }
 
     /// Sets the output speed.
+    ///
+    /// No requirements are checked, see `try_set_output_speed()`.
     pub fn set_output_speed(&mut self, output_speed: OutputSpeed) {
         match self.port {
             Port::A => unsafe {
@@ -556,6 +1653,43 @@ impl Pin {
         }
     }
 
+    /// Returns the currently configured pull-up/pull-down mode.
+    ///
+    /// The reserved value 0b11 is returned as `PullMode::Floating`.
+    pub fn pull_mode(&self) -> PullMode {
+        let pupdr = match self.port {
+            Port::A => unsafe { (*pac::GPIOA::ptr()).gpioa_pupdr.read().bits() },
+            Port::B => unsafe { (*pac::GPIOB::ptr()).gpiob_pupdr.read().bits() },
+            Port::C => unsafe { (*pac::GPIOC::ptr()).gpioc_pupdr.read().bits() },
+            Port::D => unsafe { (*pac::GPIOD::ptr()).gpiod_pupdr.read().bits() },
+            Port::E => unsafe { (*pac::GPIOE::ptr()).gpioe_pupdr.read().bits() },
+            Port::F => unsafe { (*pac::GPIOF::ptr()).gpiof_pupdr.read().bits() },
+            Port::G => unsafe { (*pac::GPIOG::ptr()).gpiog_pupdr.read().bits() },
+            Port::H => unsafe { (*pac::GPIOH::ptr()).gpioh_pupdr.read().bits() },
+            Port::I => unsafe { (*pac::GPIOI::ptr()).gpioi_pupdr.read().bits() },
+            Port::J => unsafe { (*pac::GPIOJ::ptr()).gpioj_pupdr.read().bits() },
+            Port::K => unsafe { (*pac::GPIOK::ptr()).gpiok_pupdr.read().bits() },
+            Port::Z => unsafe { (*pac::GPIOZ::ptr()).gpioz_pupdr.read().bits() },
+        };
+
+        match BitWorker::new(pupdr).subvalue(self.pin * 2, 2) {
+            0b01 => PullMode::PullUp,
+            0b10 => PullMode::PullDown,
+            _ => PullMode::Floating,
+        }
+    }
+
+    /// Panics in debug builds if the pin is an input with a pull resistor enabled.
+    ///
+    /// Useful to check that inputs driven by another device, e.g. clock inputs,
+    /// are not loaded by a pull resistor left enabled by accident.
+    pub fn assert_input_floating(&self) {
+        debug_assert!(
+            self.mode() != PinMode::Input || self.pull_mode() == PullMode::Floating,
+            "Input pin has a pull resistor enabled."
+        );
+    }
+
     /// Sets the pull-up/pull-down mode.
     pub fn set_pull_mode(&mut self, pull_mode: PullMode) {
         match self.port {
@@ -623,7 +1757,20 @@ impl Pin {
     }
 
     /// Sets the alternate function.
-    pub fn set_alternate_function(&mut self, af: u8) {
+    ///
+    /// Returns `PinError::InvalidAlternateFunction` if `af` is not in the range 0 - 15.
+    pub fn set_alternate_function(&mut self, af: u8) -> Result<(), PinError> {
+        if af > 15 {
+            return Err(PinError::InvalidAlternateFunction);
+        }
+
+        self.write_alternate_function(af);
+
+        Ok(())
+    }
+
+    /// Writes the alternate function to the AFRL or AFRH register.
+    fn write_alternate_function(&mut self, af: u8) {
         match self.port {
             Port::A => unsafe {
                 let regs = &(*pac::GPIOA::ptr());
@@ -747,6 +1894,87 @@ impl Pin {
             },
         }
     }
+
+    /// Connects the pin to its EXTI line and enables the interrupt for the edge.
+    ///
+    /// The EXTI line number is identical to the pin number, the port is selected in the
+    /// EXTICR registers of the EXTI controller. Only one port can be connected to a line
+    /// at the same time. Unlike on other STM32 families, every line 0 - 15 has its own
+    /// interrupt, see `irqn()`. The pin mode is not changed.
+    pub fn enable_interrupt(&mut self, edge: Edge) {
+        let line = self.pin;
+
+        exti::disable_interrupt(line);
+        exti::select_port(line, self.port);
+        exti::set_trigger_edge(line, edge);
+        exti::clear_pending(line);
+        exti::enable_interrupt(line);
+    }
+
+    /// Masks the interrupt of the EXTI line.
+    pub fn disable_interrupt(&mut self) {
+        exti::disable_interrupt(self.pin);
+    }
+
+    /// Returns if an edge was detected on the EXTI line.
+    pub fn is_interrupt_pending(&self) -> bool {
+        exti::is_pending(self.pin)
+    }
+
+    /// Clears the pending flags of the EXTI line.
+    pub fn clear_interrupt_pending(&mut self) {
+        exti::clear_pending(self.pin);
+    }
+
+    /// Waits asynchronuously for an edge detected by the EXTI line of the pin.
+    ///
+    /// The line is connected to the pin and armed for the edge, the interrupt is
+    /// enabled while waiting. `exti::on_interrupt()` must be called from the interrupt
+    /// handler for `irqn()` to wake the task. The previous EXTI configuration of the
+    /// line is restored when returning or when the future is dropped.
+    pub async fn wait_for_edge_async(&mut self, edge: Edge) {
+        ArmedLine::new(self, edge).wait().await
+    }
+
+    /// Waits asynchronuously for an input level.
+    ///
+    /// The edge is armed before the level is checked, so a change in between is
+    /// not missed. See `wait_for_edge_async` for the interrupt handling.
+    async fn wait_for_level_async(&mut self, state: PinState) {
+        let edge = match state {
+            PinState::High => Edge::Rising,
+            PinState::Low => Edge::Falling,
+        };
+
+        let armed = ArmedLine::new(self, edge);
+        if self.get_input_state() != state {
+            armed.wait().await;
+        }
+    }
+
+    /// Returns the interrupt number for the EXTI line of the pin, `EXTI0` - `EXTI15`.
+    #[cfg(feature = "mpu-ca7")]
+    pub fn irqn(&self) -> Irqn {
+        exti::irqn(self.pin)
+    }
+
+    /// Locks the configuration of the pin until the next reset.
+    ///
+    /// Mode, output type, speed, pull mode and alternate function can't be changed anymore
+    /// once locked. Returns `Error::LockFailed` if the lock key was not set by the sequence.
+    ///
+    /// The lock register of a port is frozen after the first lock sequence, so this fails
+    /// if another pin of the same port was locked before. Use `Port::lock_pins()` to lock
+    /// all pins of a port in one sequence.
+    pub fn lock_configuration(&mut self) -> Result<(), Error> {
+        self.port.lock_pins(1 << self.pin)
+    }
+
+    /// Returns if the configuration of the pin is locked.
+    pub fn is_configuration_locked(&self) -> bool {
+        let value = self.port.read_lckr();
+        value & LCKR_LCKK != 0 && BitWorker::new(value).is_set(self.pin)
+    }
 }
 
 impl ErrorType for Pin {
@@ -777,21 +2005,296 @@ impl OutputPin for Pin {
     }
 }
 
+impl embedded_hal_async::digital::Wait for Pin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level_async(PinState::High).await;
+
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level_async(PinState::Low).await;
+
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge_async(Edge::Rising).await;
+
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge_async(Edge::Falling).await;
+
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_edge_async(Edge::Both).await;
+
+        Ok(())
+    }
+}
+
 impl StatefulOutputPin for Pin {
     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
-        Ok(self.get_input_state() == PinState::Low)
+        Ok(self.get_output_state() == PinState::Low)
     }
 
     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
-        Ok(self.get_input_state() == PinState::High)
+        Ok(self.get_output_state() == PinState::High)
     }
 
     fn toggle(&mut self) -> Result<(), Self::Error> {
-        match self.get_input_state() {
-            PinState::Low => self.set_high(),
-            PinState::High => self.set_low(),
+        self.toggle_output_state();
+
+        Ok(())
+    }
+}
+
+/// EXTI line armed for an edge of a pin.
+///
+/// The previous configuration of the line is restored on drop.
+struct ArmedLine {
+    /// Line number.
+    line: u8,
+    /// Configuration before arming.
+    saved: exti::LineConfig,
+}
+
+impl ArmedLine {
+    /// Connects the line to the pin, arms it for the edge and clears the pending flag.
+    fn new(pin: &Pin, edge: Edge) -> Self {
+        let line = pin.pin;
+        let saved = exti::line_config(line);
+
+        exti::disable_interrupt(line);
+        exti::select_port(line, pin.port);
+        exti::set_trigger_edge(line, edge);
+        exti::clear_pending(line);
+
+        Self { line, saved }
+    }
+
+    /// Waits for the edge, enabling the interrupt of the line while waiting.
+    async fn wait(&self) {
+        let line = self.line;
+
+        poll_fn(|cx| {
+            if exti::is_pending(line) {
+                Poll::Ready(())
+            } else {
+                // The interrupt is enabled after registering the waker, an edge in
+                // between keeps the line pending and triggers it immediately.
+                exti::register_waker(line, cx.waker());
+                exti::enable_interrupt(line);
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Drop for ArmedLine {
+    fn drop(&mut self) {
+        exti::disable_interrupt(self.line);
+        exti::clear_pending(self.line);
+        exti::restore_line_config(self.line, self.saved);
+    }
+}
+
+/// Pin with register addresses resolved once for fast access.
+///
+/// Setting and reading the state is a single volatile register access without
+/// dispatching on the port, e.g. for bit-banged protocols.
+///
+/// # Example
+///
+/// Comparing the toggle rate with `Pin`, the system timer has to be running. The result
+/// depends on the core, its clock and the optimization level, so it should be measured
+/// on the target in release mode.
+///
+/// ```no_run
+/// use stm32mp15x_hal::gpio::{Pin, PinMode, PinState, Port};
+/// use stm32mp15x_hal::time::Instant;
+///
+/// const CYCLES: u32 = 100000;
+///
+/// let mut pin = Pin::new(Port::A, 13);
+/// pin.set_mode(PinMode::Output).unwrap();
+///
+/// let start = Instant::now();
+/// for _ in 0..CYCLES {
+///     pin.set_output_state(PinState::High);
+///     pin.set_output_state(PinState::Low);
+/// }
+/// let pin_micros = start.elapsed_micros();
+///
+/// let mut pin = pin.into_fast();
+///
+/// let start = Instant::now();
+/// for _ in 0..CYCLES {
+///     pin.set_high();
+///     pin.set_low();
+/// }
+/// let fast_pin_micros = start.elapsed_micros();
+///
+/// // Toggle frequencies in Hz.
+/// let pin_frequency = CYCLES as u64 * 1000000 / pin_micros;
+/// let fast_pin_frequency = CYCLES as u64 * 1000000 / fast_pin_micros;
+/// ```
+#[derive(Debug)]
+pub struct FastPin {
+    /// Original pin.
+    pin: Pin,
+    /// Address of the BSRR register.
+    bsrr: *mut u32,
+    /// Address of the IDR register.
+    idr: *const u32,
+}
+
+impl FastPin {
+    /// Sets the output high.
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        unsafe { write_volatile(self.bsrr, 1 << self.pin.pin) };
+    }
+
+    /// Sets the output low.
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        unsafe { write_volatile(self.bsrr, 1 << (self.pin.pin + 16)) };
+    }
+
+    /// Returns if the input is high.
+    #[inline(always)]
+    pub fn is_high(&self) -> bool {
+        unsafe { read_volatile(self.idr) & (1 << self.pin.pin) != 0 }
+    }
+
+    /// Returns if the input is low.
+    #[inline(always)]
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+
+    /// Returns the original pin.
+    pub fn release(self) -> Pin {
+        self.pin
+    }
+}
+
+/// Button connected to an input pin, triggering an interrupt.
+///
+/// The EXTI line of the pin is used, so only one button per pin number is possible.
+/// `init()` enables the interrupt of the line in the GIC on the Cortex-A7 and in the
+/// NVIC on the Cortex-M4, the interrupt has to be dispatched to `on_interrupt()`:
+/// - Cortex-A7: The handler set with `irq::set_irq_handler()` is called with the
+///   interrupt number, `on_interrupt()` must be called if it equals `irqn()`.
+/// - Cortex-M4: `on_interrupt()` must be called from the handler of the interrupt
+///   returned by `exti::interrupt()` for the pin number, e.g. `EXTI13` for pin 13.
+///
+/// Buttons on different pin numbers can share a handler, `on_interrupt()` returns
+/// `false` if the line of the button is not pending.
+#[derive(Debug, Clone, Copy)]
+pub struct Button {
+    /// Input pin.
+    pin: Pin,
+    /// Edge to detect.
+    edge: Edge,
+    /// Debounce time.
+    debounce: Duration,
+    /// Timestamp of the last confirmed press in microseconds.
+    last_press: Option<u64>,
+    /// Function called on a confirmed press.
+    callback: fn(),
+}
+
+impl Button {
+    /// Returns a button.
+    /// - `pin`: Input pin.
+    /// - `edge`: Edge that is treated as press, e.g. `Edge::Falling` for a button to ground.
+    /// - `debounce`: Time after a press in which further edges are ignored.
+    /// - `callback`: Function called on a confirmed press.
+    pub fn new(pin: Pin, edge: Edge, debounce: Duration, callback: fn()) -> Self {
+        Self {
+            pin,
+            edge,
+            debounce,
+            last_press: None,
+            callback,
         }
     }
+
+    /// Configures the pin as input, connects it to the EXTI line and enables the interrupt.
+    pub fn init(&mut self) {
+        let line = self.pin.pin;
+
+        self.pin.apply_mode(PinMode::Input);
+
+        exti::disable_interrupt(line);
+        exti::select_port(line, self.pin.port);
+        exti::set_trigger_edge(line, self.edge);
+        exti::clear_pending(line);
+        exti::enable_interrupt(line);
+
+        #[cfg(feature = "mpu-ca7")]
+        irq::enable_irq(self.irqn());
+
+        // Unmasking is only unsafe in critical sections relying on the mask.
+        #[cfg(feature = "mcu-cm4")]
+        unsafe {
+            cortex_m::peripheral::NVIC::unmask(exti::interrupt(line));
+        }
+    }
+
+    /// Disables the interrupt.
+    pub fn deinit(&mut self) {
+        exti::disable_interrupt(self.pin.pin);
+        exti::clear_pending(self.pin.pin);
+    }
+
+    /// Returns the interrupt number for the EXTI line of the pin.
+    #[cfg(feature = "mpu-ca7")]
+    pub fn irqn(&self) -> Irqn {
+        exti::irqn(self.pin.pin)
+    }
+
+    /// Handles the interrupt. Returns `true` if the interrupt was caused by this button.
+    ///
+    /// Edges within the debounce time after the last press are ignored. A press is only
+    /// confirmed if the pin state matches the edge, which filters out release bounces.
+    pub fn on_interrupt(&mut self) -> bool {
+        let line = self.pin.pin;
+
+        if !exti::is_pending(line) {
+            return false;
+        }
+
+        exti::clear_pending(line);
+
+        let now = time::micros();
+
+        if let Some(last_press) = self.last_press {
+            if now - last_press < self.debounce.as_micros() as u64 {
+                return true;
+            }
+        }
+
+        let confirmed = match self.edge {
+            Edge::Rising => self.pin.get_input_state() == PinState::High,
+            Edge::Falling => self.pin.get_input_state() == PinState::Low,
+            Edge::Both => true,
+        };
+
+        if confirmed {
+            self.last_press = Some(now);
+            (self.callback)();
+        }
+
+        true
+    }
 }
 
 /// Initializes the clocks for all ports.
@@ -855,6 +2358,92 @@ pub fn init() {
     }
 }
 
+/// Enables the clock of a single port.
+pub fn enable_port(port: Port) {
+    set_port_clock(port, true);
+}
+
+/// Disables the clock of a single port.
+///
+/// The pins of the port keep their configuration, but can't be accessed anymore.
+pub fn disable_port(port: Port) {
+    set_port_clock(port, false);
+}
+
+/// Sets or clears the clock enable bit of a port for the current core.
+///
+/// Ports A - K are in the AHB4 enable registers at bit 0 - 10, port Z in the AHB5
+/// enable registers at bit 0.
+fn set_port_clock(port: Port, enable: bool) {
+    let bit = match port {
+        Port::Z => 1,
+        _ => 1 << (port as u32),
+    };
+
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+
+        #[cfg(feature = "mpu-ca7")]
+        match (port, enable) {
+            (Port::Z, true) => rcc.rcc_mp_ahb5ensetr.write(|w| w.bits(bit)),
+            (Port::Z, false) => rcc.rcc_mp_ahb5enclrr.write(|w| w.bits(bit)),
+            (_, true) => rcc.rcc_mp_ahb4ensetr.write(|w| w.bits(bit)),
+            (_, false) => rcc.rcc_mp_ahb4enclrr.write(|w| w.bits(bit)),
+        }
+
+        #[cfg(feature = "mcu-cm4")]
+        match (port, enable) {
+            (Port::Z, true) => rcc.rcc_mc_ahb5ensetr.write(|w| w.bits(bit)),
+            (Port::Z, false) => rcc.rcc_mc_ahb5enclrr.write(|w| w.bits(bit)),
+            (_, true) => rcc.rcc_mc_ahb4ensetr.write(|w| w.bits(bit)),
+            (_, false) => rcc.rcc_mc_ahb4enclrr.write(|w| w.bits(bit)),
+        }
+    }
+}
+
+/// Enables the I/O compensation cell and waits until it is ready.
+///
+/// The compensation cell adjusts the slew rate to the process, voltage and temperature
+/// conditions and should be enabled when pins are used with `OutputSpeed::VeryHigh`.
+/// Returns `Error::CompensationTimeout` if the cell doesn't get ready in time.
+pub fn enable_io_compensation() -> Result<(), Error> {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        let syscfg = &(*pac::SYSCFG::ptr());
+
+        // The enable bits are missing in PAC, so handle them manually:
+        // MPU_EN (bit 0) and MCU_EN (bit 1) in CMPENSETR, READY (bit 8) in CMPCR.
+        #[cfg(feature = "mpu-ca7")]
+        {
+            rcc.rcc_mp_apb3ensetr.modify(|_, w| w.syscfgen().set_bit());
+            syscfg.syscfg_cmpensetr.write(|w| w.bits(1 << 0));
+        }
+
+        #[cfg(feature = "mcu-cm4")]
+        {
+            rcc.rcc_mc_apb3ensetr.modify(|_, w| w.syscfgen().set_bit());
+            syscfg.syscfg_cmpensetr.write(|w| w.bits(1 << 1));
+        }
+    }
+
+    let start_time = time::Instant::now();
+    while !is_io_compensation_ready() {
+        if start_time.is_elapsed_millis(COMPENSATION_TIMEOUT) {
+            return Err(Error::CompensationTimeout);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns if the I/O compensation cell is enabled and ready.
+pub fn is_io_compensation_ready() -> bool {
+    let syscfg = unsafe { &(*pac::SYSCFG::ptr()) };
+
+    // READY (bit 8) in CMPCR is missing in PAC.
+    syscfg.syscfg_cmpcr.read().bits() & (1 << 8) != 0
+}
+
 /// Returns the modified MODER register value for a specific pin and mode.
 fn modr(value: u32, pin: u8, mode: PinMode) -> u32 {
     BitWorker::new(value)
@@ -872,6 +2461,27 @@ fn bsrr(pin: u8, state: PinState) -> u32 {
     BitWorker::new(0).set(position).value()
 }
 
+/// Returns the BSRR register value that writes a value to a range of pins only.
+fn masked_bsrr(start_pin: u8, pin_count: u8, value: u32) -> u32 {
+    let mask = bitmask(pin_count, start_pin) & 0xFFFF;
+    let set = (value << start_pin) & mask;
+    let reset = !set & mask;
+    set | (reset << 16)
+}
+
+/// Returns the BSRR register value that inverts a specific pin of an ODR register value.
+fn toggle_pin_bsrr(odr_value: u32, pin: u8) -> u32 {
+    bsrr(pin, !odr(odr_value, pin))
+}
+
+/// Returns the BSRR register value that toggles the masked pins of an ODR register value.
+fn toggle_bsrr(odr: u32, mask: u16) -> u32 {
+    let mask = mask as u32;
+    let high = odr & mask;
+    let low = !odr & mask;
+    low | (high << 16)
+}
+
 /// Returns the modified OSPEEDR register value for a specific pin and speed.
 fn ospeedr(value: u32, pin: u8, output_speed: OutputSpeed) -> u32 {
     BitWorker::new(value)
@@ -895,6 +2505,15 @@ fn idr(value: u32, pin: u8) -> PinState {
     }
 }
 
+/// Returns the state from the ODR register value for a specific pin.
+fn odr(value: u32, pin: u8) -> PinState {
+    if BitWorker::new(value).is_set(pin) {
+        PinState::High
+    } else {
+        PinState::Low
+    }
+}
+
 /// Returns the modified PUPDR register value for a specific pin and pull mode.
 fn pupdr(value: u32, pin: u8, pull_mode: PullMode) -> u32 {
     BitWorker::new(value)
@@ -906,3 +2525,70 @@ fn pupdr(value: u32, pin: u8, pull_mode: PullMode) -> u32 {
 fn afr(value: u32, pin: u8, af: u8) -> u32 {
     BitWorker::new(value).replace(af as u32, pin * 4, 4).value()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the ODR register value after writing a BSRR value, set has priority.
+    fn apply_bsrr(odr: u32, bsrr: u32) -> u32 {
+        let set = bsrr & 0xFFFF;
+        let reset = bsrr >> 16;
+        ((odr & !reset) | set) & 0xFFFF
+    }
+
+    #[test]
+    fn output_state_read_back_from_odr() {
+        let mut value = 0;
+        for pin in 0..16 {
+            value = apply_bsrr(value, bsrr(pin, PinState::High));
+            assert_eq!(odr(value, pin), PinState::High);
+        }
+        assert_eq!(value, 0xFFFF);
+
+        value = apply_bsrr(value, bsrr(5, PinState::Low));
+        assert_eq!(odr(value, 5), PinState::Low);
+        assert_eq!(value, 0xFFDF);
+    }
+
+    #[test]
+    fn output_state_ignores_input_level() {
+        // Pin 3 driven high while an external pulldown holds the input low.
+        let odr_value = apply_bsrr(0, bsrr(3, PinState::High));
+        let idr_value = 0;
+
+        assert_eq!(odr(odr_value, 3), PinState::High);
+        assert_eq!(idr(idr_value, 3), PinState::Low);
+    }
+
+    #[test]
+    fn even_toggle_count_restores_level() {
+        for initial in [0x0000, 0xA5A5, 0xFFFF] {
+            let mut value = initial;
+            for _ in 0..4 {
+                value = apply_bsrr(value, toggle_pin_bsrr(value, 7));
+            }
+            assert_eq!(value, initial);
+
+            value = apply_bsrr(value, toggle_pin_bsrr(value, 7));
+            assert_eq!(value, initial ^ (1 << 7));
+        }
+    }
+
+    #[test]
+    fn masked_bus_write_leaves_other_pins() {
+        let value = apply_bsrr(0x000F, masked_bsrr(4, 4, 0b1010));
+        assert_eq!(value & 0x00F0, 0b1010 << 4);
+        assert_eq!(value & 0x000F, 0x000F);
+        assert_eq!(masked_bsrr(4, 4, 0b1010), 0b1010_0000 | (0b0101_0000 << 16));
+
+        // Bits of the value beyond the pin count are ignored.
+        assert_eq!(masked_bsrr(4, 4, 0xFF), 0b1111_0000);
+    }
+
+    #[test]
+    fn bsrr_writes_single_pin() {
+        assert_eq!(bsrr(3, PinState::High), 1 << 3);
+        assert_eq!(bsrr(3, PinState::Low), 1 << 19);
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Timing calculation taken from <https://github.com/David-OConnor/stm32-hal/blob/main/src/i2c.rs>
 
-// Todo: error handling, timeouts, DMA, 10-bit addresses, slave mode.
+// Todo: slave mode.
 
 use core::future::poll_fn;
 use core::marker::PhantomData;
@@ -12,19 +12,39 @@ use core::task::Poll;
 use cfg_if::cfg_if;
 use embedded_hal as eh;
 
+use crate::dma::{DmaConfigError, DmaRequestInput, DmaStream, DmaStreamConfig, TransferDirection};
+use crate::gpio::{OutputType, Pin, PinMode, PinState};
 use crate::pac;
 use crate::rcc;
+use crate::time::{self, Instant};
 use pac::i2c1::RegisterBlock;
 use pac::{I2C1, I2C2, I2C3, I2C4, I2C5, I2C6};
 
 /// I2C peripheral.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct I2c<R>
 where
     R: Deref<Target = RegisterBlock>,
 {
     /// Phantom register block.
     _regs: PhantomData<R>,
+    /// Timeout in milliseconds for a flag to be set in blocking transfers.
+    timeout: u64,
+    /// Configuration applied by `init`, reapplied after a bus recovery.
+    config: Option<I2cConfig>,
+}
+
+impl<R> Default for I2c<R>
+where
+    R: Deref<Target = RegisterBlock>,
+{
+    fn default() -> Self {
+        Self {
+            _regs: PhantomData,
+            timeout: FLAG_TIMEOUT,
+            config: None,
+        }
+    }
 }
 
 /// Type alias for I2C1.
@@ -45,6 +65,51 @@ pub type I2c5 = I2c<I2C5>;
 /// Type alias for I2C6.
 pub type I2c6 = I2c<I2C6>;
 
+/// Default timeout in milliseconds for a flag to be set in blocking transfers.
+const FLAG_TIMEOUT: u64 = 100;
+
+/// Half period of the SCL clock in microseconds during bus recovery.
+const RECOVERY_HALF_PERIOD_US: u32 = 5;
+
+/// ISR transmit data register empty flag.
+const ISR_TXE: u32 = 1 << 0;
+
+/// ISR receive data register not empty flag.
+const ISR_RXNE: u32 = 1 << 2;
+
+/// ISR not acknowledge received flag.
+const ISR_NACKF: u32 = 1 << 4;
+
+/// ISR stop detection flag.
+const ISR_STOPF: u32 = 1 << 5;
+
+/// ISR transfer complete flag.
+const ISR_TC: u32 = 1 << 6;
+
+/// ISR transfer complete reload flag.
+const ISR_TCR: u32 = 1 << 7;
+
+/// ISR bus error flag.
+const ISR_BERR: u32 = 1 << 8;
+
+/// ISR arbitration lost flag.
+const ISR_ARLO: u32 = 1 << 9;
+
+/// ISR overrun/underrun flag.
+const ISR_OVR: u32 = 1 << 10;
+
+/// ISR PEC error in reception flag.
+const ISR_PECERR: u32 = 1 << 11;
+
+/// ISR bus busy flag.
+const ISR_BUSY: u32 = 1 << 15;
+
+/// ISR error flags handled by `take_error()`.
+const ISR_ERRORS: u32 = ISR_NACKF | ISR_BERR | ISR_ARLO | ISR_OVR;
+
+/// Maximum number of bytes per NBYTES reload.
+const MAX_NBYTES: usize = 255;
+
 // ------------------------- Configuration ---------------------------
 
 /// Configuration settings.
@@ -52,13 +117,113 @@ pub type I2c6 = I2c<I2C6>;
 pub struct I2cConfig {
     /// Clock speed.
     pub speed: I2cSpeed,
+    /// Own address 2 for slave mode, disabled if `None`.
+    pub own_address2: Option<OwnAddress2>,
+    /// Timeout in milliseconds for a flag to be set in blocking transfers.
+    ///
+    /// Prevents hanging forever when the bus is stuck, e.g. a slave holding SCL low.
+    pub timeout: u64,
+    /// SMBus host mode with packet error checking and alert detection.
+    ///
+    /// Enables the hardware PEC engine used by `write_pec()` and `write_read_pec()`.
+    /// Alerts are only detected if the SMBA pin is configured.
+    pub smbus: bool,
+    /// Analog noise filter, suppressing spikes shorter than 50ns.
+    pub analog_filter: bool,
+    /// Digital noise filter length in kernel clock periods, range is 0-15, 0 disables it.
+    ///
+    /// On noisy buses, e.g. in Fast Mode Plus, disabling the analog filter and using
+    /// the digital filter instead can avoid spurious errors.
+    pub digital_filter: u8,
+    /// Acknowledge the general call address 0x00 in slave mode.
+    pub general_call_enable: bool,
+    /// Disable clock stretching in slave mode.
+    ///
+    /// Must be off in master mode.
+    pub no_stretch: bool,
+    /// Raw TIMINGR value, e.g. from CubeMX, replacing the calculated timing if `Some`.
+    ///
+    /// `speed` is ignored in this case.
+    pub timing_override: Option<u32>,
 }
 
 impl Default for I2cConfig {
     fn default() -> Self {
         Self {
             speed: I2cSpeed::Standard,
+            own_address2: None,
+            timeout: FLAG_TIMEOUT,
+            smbus: false,
+            analog_filter: true,
+            digital_filter: 0,
+            general_call_enable: false,
+            no_stretch: false,
+            timing_override: None,
+        }
+    }
+}
+
+impl I2cConfig {
+    /// Checks the configuration for values out of range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.digital_filter > 15 {
+            return Err(ConfigError::DigitalFilter);
+        }
+
+        if let Some(own_address2) = self.own_address2 {
+            if own_address2.address > 0x7F {
+                return Err(ConfigError::OwnAddress2);
+            }
+            if own_address2.mask_bits > 7 {
+                return Err(ConfigError::OwnAddress2Mask);
+            }
         }
+
+        Ok(())
+    }
+}
+
+/// Own address 2 settings.
+///
+/// With a mask, the peripheral acknowledges a block of `2 ^ mask_bits` consecutive
+/// addresses. The address that matched can be read with `matched_address()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OwnAddress2 {
+    /// 7-bit address.
+    pub address: u8,
+    /// Number of least significant address bits ignored in the comparison, range is 0-7.
+    pub mask_bits: u8,
+}
+
+/// Slave address.
+///
+/// A plain `u8` converts into a 7-bit address.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Address {
+    /// 7-bit address.
+    SevenBit(u8),
+    /// 10-bit address.
+    TenBit(u16),
+}
+
+impl Address {
+    /// Returns the value for the SADD field in CR2.
+    fn sadd(&self) -> u16 {
+        match self {
+            Address::SevenBit(address) => (*address as u16 & 0x7F) << 1,
+            Address::TenBit(address) => *address & 0x3FF,
+        }
+    }
+
+    /// Returns if this is a 10-bit address.
+    fn is_ten_bit(&self) -> bool {
+        matches!(self, Address::TenBit(_))
+    }
+}
+
+impl From<u8> for Address {
+    fn from(value: u8) -> Self {
+        Address::SevenBit(value)
     }
 }
 
@@ -84,6 +249,54 @@ impl I2cSpeed {
     }
 }
 
+// ----------------------------- Errors -------------------------------
+
+/// Configuration errors.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConfigError {
+    /// Own address 2 exceeds 7 bits.
+    OwnAddress2,
+    /// Own address 2 mask exceeds 7 bits.
+    OwnAddress2Mask,
+    /// Digital filter length exceeds 15.
+    DigitalFilter,
+    /// Speed not achievable with the current kernel clock frequency.
+    Timing,
+}
+
+/// Errors of DMA and SMBus transfers.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Error {
+    /// No DMA request input available for the instance.
+    NoDmaRequest,
+    /// DMA transfer error.
+    DmaTransfer,
+    /// Error on the bus.
+    Bus(eh::i2c::ErrorKind),
+    /// Packet error checking is not enabled in the configuration.
+    PecDisabled,
+    /// Received PEC doesn't match.
+    Pec,
+    /// Transfer size not supported.
+    TransferSize,
+    /// DMA stream configuration error.
+    DmaConfig(DmaConfigError),
+}
+
+impl From<DmaConfigError> for Error {
+    fn from(value: DmaConfigError) -> Self {
+        Error::DmaConfig(value)
+    }
+}
+
+impl From<eh::i2c::ErrorKind> for Error {
+    fn from(value: eh::i2c::ErrorKind) -> Self {
+        Error::Bus(value)
+    }
+}
+
 // ------------------------- Implementation ---------------------------
 
 impl<R> I2c<R>
@@ -92,156 +305,1025 @@ where
 {
     /// Returns the peripheral instance.
     pub fn new() -> Self {
-        Self { _regs: PhantomData }
+        Self::default()
+    }
+
+    /// Initializes the peripheral.
+    pub fn init(&mut self, config: I2cConfig) -> Result<(), ConfigError> {
+        config.validate()?;
+        self.config = None;
+
+        R::enable_clock();
+
+        self.disable();
+        self.timeout = config.timeout;
+
+        let regs = R::registers();
+
+        match config.timing_override {
+            Some(timing) => unsafe {
+                regs.i2c_timingr.write(|w| w.bits(timing));
+            },
+            None => self.set_timing(config.speed)?,
+        }
+
+        regs.i2c_icr.write(|w| {
+            w.addrcf()
+                .set_bit()
+                .nackcf()
+                .set_bit()
+                .stopcf()
+                .set_bit()
+                .berrcf()
+                .set_bit()
+                .arlocf()
+                .set_bit()
+                .ovrcf()
+                .set_bit()
+                .peccf()
+                .set_bit()
+                .timoutcf()
+                .set_bit()
+                .alertcf()
+                .set_bit()
+        });
+
+        // OAR2 can only be written while the address is disabled.
+        regs.i2c_oar2.modify(|_, w| w.oa2en().clear_bit());
+        if let Some(own_address2) = config.own_address2 {
+            unsafe {
+                regs.i2c_oar2.modify(|_, w| {
+                    w.oa2()
+                        .bits(own_address2.address)
+                        .oa2msk()
+                        .bits(own_address2.mask_bits)
+                });
+            }
+            regs.i2c_oar2.modify(|_, w| w.oa2en().set_bit());
+        }
+
+        // The filters and clock stretching can only be changed while the peripheral
+        // is disabled.
+        unsafe {
+            regs.i2c_cr1.modify(|_, w| {
+                w.pecen()
+                    .bit(config.smbus)
+                    .smbhen()
+                    .bit(config.smbus)
+                    .alerten()
+                    .bit(config.smbus)
+                    .anfoff()
+                    .bit(!config.analog_filter)
+                    .dnf()
+                    .bits(config.digital_filter)
+                    .gcen()
+                    .bit(config.general_call_enable)
+                    .nostretch()
+                    .bit(config.no_stretch)
+            });
+        }
+
+        self.enable();
+        self.config = Some(config);
+
+        Ok(())
+    }
+
+    /// Calculates the timing register values for a speed and writes them.
+    fn set_timing(&mut self, speed: I2cSpeed) -> Result<(), ConfigError> {
+        let presc_const = match speed {
+            I2cSpeed::Standard => 4_000_000,
+            I2cSpeed::Fast => 8_000_000,
+            I2cSpeed::FastPlus => 8_000_000,
+        };
+
+        let presc_val = (R::clock_frequency() as u32 / presc_const).min(16);
+
+        let scll_val = if presc_val == 16 {
+            (R::clock_frequency() as u32 / presc_val) / (2 * speed.hz())
+        } else {
+            presc_const / (2 * speed.hz())
+        };
+
+        let sclh_val = match speed {
+            I2cSpeed::Standard => scll_val.checked_sub(4).ok_or(ConfigError::Timing)?,
+            I2cSpeed::Fast => scll_val * 4 / 10,
+            I2cSpeed::FastPlus => scll_val / 2,
+        };
+
+        // A kernel clock that is too low for the requested speed results in values
+        // of zero here, which must not wrap around.
+        let presc = presc_val.checked_sub(1).ok_or(ConfigError::Timing)?;
+        let scll = scll_val.checked_sub(1).ok_or(ConfigError::Timing)?;
+        let sclh = sclh_val.checked_sub(1).ok_or(ConfigError::Timing)?;
+
+        let sdadel = match speed {
+            I2cSpeed::Standard => 0x2,
+            I2cSpeed::Fast => 0x3,
+            I2cSpeed::FastPlus => 0x0,
+        };
+
+        let scldel = match speed {
+            I2cSpeed::Standard => 0x4,
+            I2cSpeed::Fast => 0x3,
+            I2cSpeed::FastPlus => 0x1,
+        };
+
+        assert!(scldel <= 15);
+        assert!(sdadel <= 15);
+
+        if presc > 15 || scll > 255 || sclh > 255 {
+            return Err(ConfigError::Timing);
+        }
+
+        let regs = R::registers();
+
+        unsafe {
+            regs.i2c_timingr.write(|w| {
+                w.presc()
+                    .bits(presc as u8)
+                    .scldel()
+                    .bits(scldel as u8)
+                    .sdadel()
+                    .bits(sdadel as u8)
+                    .sclh()
+                    .bits(sclh as u8)
+                    .scll()
+                    .bits(scll as u8)
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Deinitializes the peripheral.
+    ///
+    /// The peripheral is reset before the clock is disabled, so a later `init()` starts
+    /// from the reset state of all registers.
+    pub fn deinit(&mut self) {
+        self.disable();
+        R::reset();
+        R::disable_clock();
+    }
+
+    /// Writes bytes to the slave using DMA, blocking.
+    ///
+    /// The stream is configured for the TX request of the instance and released
+    /// after the transfer. Transfers larger than 255 bytes are split using NBYTES reload,
+    /// the maximum is 65535 bytes.
+    pub fn write_dma(
+        &mut self,
+        address: impl Into<Address>,
+        write: &[u8],
+        stream: DmaStream,
+    ) -> Result<(), Error> {
+        let request_input = R::tx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if write.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        let regs = R::registers();
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::MemoryToPeripheral,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(write.as_ptr() as u32)?;
+        stream.init(config)?;
+        stream.start_transfer(
+            write.as_ptr() as u32,
+            regs.i2c_txdr.as_ptr() as u32,
+            write.len(),
+        );
+        regs.i2c_cr1.modify(|_, w| w.txdmaen().set_bit());
+
+        let result = self.transfer_dma(address.into(), write.len(), stream, false);
+
+        regs.i2c_cr1.modify(|_, w| w.txdmaen().clear_bit());
+        stream.stop_transfer();
+
+        result
+    }
+
+    /// Reads bytes from the slave using DMA, blocking.
+    ///
+    /// The stream is configured for the RX request of the instance and released
+    /// after the transfer. Transfers larger than 255 bytes are split using NBYTES reload,
+    /// the maximum is 65535 bytes.
+    pub fn read_dma(
+        &mut self,
+        address: impl Into<Address>,
+        read: &mut [u8],
+        stream: DmaStream,
+    ) -> Result<(), Error> {
+        let request_input = R::rx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if read.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        let regs = R::registers();
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::PeripheralToMemory,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(read.as_mut_ptr() as u32)?;
+        stream.init(config)?;
+        stream.start_transfer(
+            read.as_mut_ptr() as u32,
+            regs.i2c_rxdr.as_ptr() as u32,
+            read.len(),
+        );
+        regs.i2c_cr1.modify(|_, w| w.rxdmaen().set_bit());
+
+        let result = self.transfer_dma(address.into(), read.len(), stream, true);
+
+        regs.i2c_cr1.modify(|_, w| w.rxdmaen().clear_bit());
+        stream.stop_transfer();
+
+        result
+    }
+
+    /// Runs a transfer with a started DMA stream until the stop condition.
+    ///
+    /// Returns an error if the stream makes no progress within the timeout, e.g. when
+    /// the clock is stretched by the slave.
+    fn transfer_dma(
+        &mut self,
+        address: Address,
+        len: usize,
+        stream: DmaStream,
+        read: bool,
+    ) -> Result<(), Error> {
+        let regs = R::registers();
+        let nack_source = if read {
+            eh::i2c::NoAcknowledgeSource::Address
+        } else {
+            eh::i2c::NoAcknowledgeSource::Unknown
+        };
+
+        self.wait_for_idle()?;
+
+        self.set_address(address, read, false);
+        self.set_transfer_size(len, true);
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+
+        let mut remaining = len;
+        let mut start = Instant::now();
+        let mut remaining_transfers = stream.remaining_transfers();
+
+        loop {
+            let isr = regs.i2c_isr.read().bits();
+
+            if isr & ISR_STOPF != 0 {
+                break;
+            }
+
+            // The timeout restarts whenever a byte is transferred.
+            if stream.remaining_transfers() != remaining_transfers {
+                remaining_transfers = stream.remaining_transfers();
+                start = Instant::now();
+            } else if start.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return Err(Error::Bus(eh::i2c::ErrorKind::Other));
+            }
+
+            if let Some(error) = self.take_error(isr, nack_source) {
+                return Err(Error::Bus(error));
+            }
+
+            if stream.is_transfer_error() {
+                self.reset_state();
+                return Err(Error::DmaTransfer);
+            }
+
+            if isr & ISR_TCR != 0 {
+                remaining -= MAX_NBYTES;
+                self.set_transfer_size(remaining, true);
+            }
+        }
+
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        Ok(())
+    }
+
+    /// Writes bytes followed by the packet error code to the slave, blocking.
+    ///
+    /// The PEC byte is calculated and appended by the hardware. Requires `smbus` to be
+    /// enabled in the configuration, the number of bytes is limited to 254.
+    pub fn write_pec(&mut self, address: impl Into<Address>, write: &[u8]) -> Result<(), Error> {
+        self.check_pec_transfer(write.len())?;
+        self.wait_for_idle()?;
+
+        let regs = R::registers();
+        let nack_source = eh::i2c::NoAcknowledgeSource::Unknown;
+
+        self.set_address(address.into(), false, false);
+        self.set_transfer_size(write.len() + 1, true);
+        regs.i2c_cr2.modify(|_, w| w.pecbyte().set_bit());
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+
+        for byte in write {
+            self.wait_for_flags(ISR_TXE, nack_source)?;
+            regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
+        }
+
+        self.wait_for_flags(ISR_STOPF, nack_source)?;
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        Ok(())
+    }
+
+    /// Writes bytes to the slave, then reads bytes followed by the packet error code
+    /// back using RESTART, blocking.
+    ///
+    /// The received PEC byte is verified by the hardware over the whole transaction.
+    /// Returns `Error::Pec` on a mismatch. Requires `smbus` to be enabled in the
+    /// configuration, the number of bytes read is limited to 254.
+    pub fn write_read_pec(
+        &mut self,
+        address: impl Into<Address>,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error> {
+        self.check_pec_transfer(read.len())?;
+        if write.len() > MAX_NBYTES {
+            return Err(Error::TransferSize);
+        }
+        self.wait_for_idle()?;
+
+        let regs = R::registers();
+        let address = address.into();
+
+        self.set_address(address, false, false);
+        self.set_transfer_size(write.len(), false);
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+
+        for (i, byte) in write.iter().enumerate() {
+            self.wait_for_flags(ISR_TXE, write_nack_source(i))?;
+            regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
+        }
+        self.wait_for_flags(ISR_TC, write_nack_source(write.len()))?;
+
+        let nack_source = eh::i2c::NoAcknowledgeSource::Address;
+
+        self.set_address(address, true, true);
+        self.set_transfer_size(read.len() + 1, true);
+        regs.i2c_cr2.modify(|_, w| w.pecbyte().set_bit());
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+
+        for byte in read.iter_mut() {
+            self.wait_for_flags(ISR_RXNE, nack_source)?;
+            *byte = regs.i2c_rxdr.read().rxdata().bits();
+        }
+
+        // The PEC byte is also put into the receive data register.
+        self.wait_for_flags(ISR_RXNE | ISR_STOPF, nack_source)?;
+        regs.i2c_rxdr.read();
+        self.wait_for_flags(ISR_STOPF, nack_source)?;
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        if regs.i2c_isr.read().bits() & ISR_PECERR != 0 {
+            regs.i2c_icr.write(|w| w.peccf().set_bit());
+            return Err(Error::Pec);
+        }
+
+        Ok(())
+    }
+
+    /// Returns if an SMBus alert was detected on the SMBA pin.
+    pub fn is_smbus_alert(&self) -> bool {
+        let regs = R::registers();
+        regs.i2c_isr.read().alert().bit_is_set()
+    }
+
+    /// Clears the SMBus alert flag.
+    pub fn clear_smbus_alert(&mut self) {
+        let regs = R::registers();
+        regs.i2c_icr.write(|w| w.alertcf().set_bit());
+    }
+
+    /// Checks if PEC is enabled and the number of bytes plus PEC fits into NBYTES.
+    fn check_pec_transfer(&self, len: usize) -> Result<(), Error> {
+        let regs = R::registers();
+
+        if regs.i2c_cr1.read().pecen().bit_is_clear() {
+            return Err(Error::PecDisabled);
+        }
+
+        if len >= MAX_NBYTES {
+            return Err(Error::TransferSize);
+        }
+
+        Ok(())
+    }
+
+    /// Waits for any ongoing operation to be finished, blocking.
+    fn wait_for_idle(&mut self) -> Result<(), eh::i2c::ErrorKind> {
+        let regs = R::registers();
+        let start = Instant::now();
+
+        while regs.i2c_isr.read().bits() & ISR_BUSY != 0 {
+            if start.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return Err(eh::i2c::ErrorKind::Other);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recovers a bus blocked by a slave holding SDA low.
+    ///
+    /// The pins are temporarily used as open-drain GPIO outputs. SCL is clocked up to
+    /// 9 times until the slave releases SDA, followed by a STOP condition. Afterwards
+    /// the previous pin modes are restored, while the output type stays open-drain as
+    /// required for I2C. The peripheral is reset and the configuration of the last
+    /// `init` is applied again.
+    ///
+    /// Returns if SDA was released, or an error if the configuration can't be applied,
+    /// e.g. because the kernel clock was changed.
+    pub fn recover_bus(&mut self, mut scl: Pin, mut sda: Pin) -> Result<bool, ConfigError> {
+        let scl_mode = scl.mode();
+        let sda_mode = sda.mode();
+
+        self.disable();
+
+        for pin in [&mut scl, &mut sda] {
+            pin.set_output_type(OutputType::OpenDrain);
+            pin.set_output_state(PinState::High);
+            pin.apply_mode(PinMode::Output);
+        }
+        time::delay_us(RECOVERY_HALF_PERIOD_US);
+
+        for _ in 0..9 {
+            if sda.get_input_state() == PinState::High {
+                break;
+            }
+            scl.set_output_state(PinState::Low);
+            time::delay_us(RECOVERY_HALF_PERIOD_US);
+            scl.set_output_state(PinState::High);
+            time::delay_us(RECOVERY_HALF_PERIOD_US);
+        }
+
+        // STOP condition: SDA rising while SCL is high.
+        scl.set_output_state(PinState::Low);
+        time::delay_us(RECOVERY_HALF_PERIOD_US);
+        sda.set_output_state(PinState::Low);
+        time::delay_us(RECOVERY_HALF_PERIOD_US);
+        scl.set_output_state(PinState::High);
+        time::delay_us(RECOVERY_HALF_PERIOD_US);
+        sda.set_output_state(PinState::High);
+        time::delay_us(RECOVERY_HALF_PERIOD_US);
+
+        let released = sda.get_input_state() == PinState::High;
+
+        scl.apply_mode(scl_mode);
+        sda.apply_mode(sda_mode);
+
+        R::reset();
+        match self.config.take() {
+            Some(config) => self.init(config)?,
+            None => self.enable(),
+        }
+
+        Ok(released)
     }
 
-    /// Initializes the peripheral.
-    pub fn init(&mut self, config: I2cConfig) {
-        R::enable_clock();
+    /// Returns if a device responds at the specified address.
+    ///
+    /// Returns `false` if the bus stays busy or the transfer doesn't finish in time.
+    pub fn is_device_ready(&mut self, address: u8) -> bool {
+        let regs = R::registers();
+
+        // Wait for any ongoing operation to be finished.
+        let start_time = Instant::now();
+        while regs.i2c_isr.read().busy().bit_is_set() {
+            if start_time.is_elapsed_millis(self.timeout) {
+                return false;
+            }
+        }
+
+        // Clear NACK and STOP flags.
+        regs.i2c_icr
+            .write(|w| w.nackcf().set_bit().stopcf().set_bit());
+
+        // The address mode and reload of a previous transfer must not be kept.
+        self.set_address(Address::SevenBit(address), false, false);
+        self.set_transfer_size(0, true);
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+
+        let start_time = Instant::now();
+        while regs.i2c_isr.read().stopf().bit_is_clear() {
+            if start_time.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return false;
+            }
+        }
+
+        let nack = regs.i2c_isr.read().nackf().bit_is_set();
+
+        if nack {
+            regs.i2c_icr
+                .write(|w| w.nackcf().set_bit().stopcf().set_bit());
+        }
+
+        !nack
+    }
+
+    /// Returns if a device responds at the specified address within a number of probes.
+    ///
+    /// The probe is repeated up to `retries` times with `delay_ms` milliseconds between
+    /// the attempts, returning `true` on the first acknowledge. This is the ACK polling
+    /// required by EEPROMs while they finish an internal write cycle.
+    pub fn is_device_ready_retries(&mut self, address: u8, retries: u32, delay_ms: u32) -> bool {
+        for attempt in 0..retries {
+            if attempt > 0 {
+                time::delay_ms(delay_ms);
+            }
+            if self.is_device_ready(address) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Probes all non-reserved 7-bit addresses 0x08 - 0x77 and returns the responding ones.
+    ///
+    /// The result is a bitmap with bit `address % 8` of byte `address / 8` set
+    /// for each device that acknowledged its address.
+    pub fn scan(&mut self) -> [u8; 16] {
+        let mut bitmap = [0; 16];
+
+        for address in 0x08..=0x77 {
+            if self.is_device_ready(address) {
+                bitmap[address as usize / 8] |= 1 << (address % 8);
+            }
+        }
+
+        bitmap
+    }
+
+    /// Reads bytes from the slave, blocking.
+    pub fn read(
+        &mut self,
+        address: impl Into<Address>,
+        read: &mut [u8],
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        self.transaction(address, &mut [eh::i2c::Operation::Read(read)])
+    }
+
+    /// Reads bytes from the slave and returns the number of received bytes, blocking.
+    ///
+    /// On error, the number of valid bytes in the buffer is returned together with
+    /// the error.
+    pub fn read_n(
+        &mut self,
+        address: impl Into<Address>,
+        read: &mut [u8],
+    ) -> Result<usize, (eh::i2c::ErrorKind, usize)> {
+        let regs = R::registers();
+
+        // Wait for any ongoing operation to be finished.
+        let start = Instant::now();
+        while regs.i2c_isr.read().bits() & ISR_BUSY != 0 {
+            if start.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return Err((eh::i2c::ErrorKind::Other, 0));
+            }
+        }
+
+        let nack_source = eh::i2c::NoAcknowledgeSource::Address;
+        let len = read.len();
+        let mut count = 0;
+
+        self.set_address(address.into(), true, false);
+        self.set_transfer_size(len, true);
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        for byte in read.iter_mut() {
+            if count > 0 && count.is_multiple_of(MAX_NBYTES) {
+                self.wait_for_flags(ISR_TCR, nack_source)
+                    .map_err(|error| (error, count))?;
+                self.set_transfer_size(len - count, true);
+            }
+            self.wait_for_flags(ISR_RXNE, nack_source)
+                .map_err(|error| (error, count))?;
+            *byte = regs.i2c_rxdr.read().rxdata().bits();
+            count += 1;
+        }
+
+        self.wait_for_flags(ISR_STOPF, nack_source)
+            .map_err(|error| (error, count))?;
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        Ok(count)
+    }
+
+    /// Writes bytes to the slave, blocking.
+    pub fn write(
+        &mut self,
+        address: impl Into<Address>,
+        write: &[u8],
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        self.transaction(address, &mut [eh::i2c::Operation::Write(write)])
+    }
+
+    /// Writes a number of bytes to the slave, then reads some bytes back using RESTART, blocking.
+    ///
+    /// This is the typical access to register based devices, e.g.
+    /// `i2c.write_read(0x48, &[register], &mut buffer)`. No STOP condition is generated
+    /// between writing and reading.
+    pub fn write_read(
+        &mut self,
+        address: impl Into<Address>,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        self.transaction(
+            address,
+            &mut [
+                eh::i2c::Operation::Write(write),
+                eh::i2c::Operation::Read(read),
+            ],
+        )
+    }
+
+    /// Reads a single register of the slave, blocking.
+    pub fn read_register(
+        &mut self,
+        address: impl Into<Address>,
+        register: u8,
+    ) -> Result<u8, eh::i2c::ErrorKind> {
+        let mut value = [0];
+        self.write_read(address, &[register], &mut value)?;
+
+        Ok(value[0])
+    }
+
+    /// Reads consecutive registers of the slave starting at `register`, blocking.
+    pub fn read_registers(
+        &mut self,
+        address: impl Into<Address>,
+        register: u8,
+        read: &mut [u8],
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        self.write_read(address, &[register], read)
+    }
+
+    /// Writes a single register of the slave, blocking.
+    ///
+    /// # Example
+    ///
+    /// Measuring the duration of a 2-byte register write, the system timer has to be
+    /// running and the pins configured. With the default 100 kHz, the bus time of the
+    /// 3 bytes including the address is about 280 µs, the remainder is the software
+    /// overhead of the flag polling. The result depends on the core, its clock and the optimization level, so
+    /// it should be measured on the target in release mode.
+    ///
+    /// ```no_run
+    /// use stm32mp15x_hal::i2c::{I2c1, I2cConfig};
+    /// use stm32mp15x_hal::time::Instant;
+    ///
+    /// const CYCLES: u32 = 1000;
+    ///
+    /// let mut i2c = I2c1::new();
+    /// i2c.init(I2cConfig::default()).unwrap();
+    ///
+    /// let start = Instant::now();
+    /// for _ in 0..CYCLES {
+    ///     i2c.write_register(0x50, 0x10, 0xA5).unwrap();
+    /// }
+    ///
+    /// // Average duration of a write in µs.
+    /// let write_micros = start.elapsed_micros() / CYCLES as u64;
+    /// ```
+    pub fn write_register(
+        &mut self,
+        address: impl Into<Address>,
+        register: u8,
+        value: u8,
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        self.write(address, &[register, value])
+    }
+
+    /// Execute operations on the bus, blocking.
+    ///
+    /// Returns `ErrorKind::NoAcknowledge` if the slave doesn't acknowledge,
+    /// `ErrorKind::Bus`, `ErrorKind::ArbitrationLoss` or `ErrorKind::Overrun` if the
+    /// matching flag is set and `ErrorKind::Other` if a flag is not set within the timeout.
+    pub fn transaction(
+        &mut self,
+        address: impl Into<Address>,
+        operations: &mut [eh::i2c::Operation<'_>],
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        let regs = R::registers();
+
+        // Wait for any ongoing operation to be finished.
+        let start = Instant::now();
+        while regs.i2c_isr.read().bits() & ISR_BUSY != 0 {
+            if start.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return Err(eh::i2c::ErrorKind::Other);
+            }
+        }
+
+        let address = address.into();
+        let mut operations = operations.iter_mut().peekable();
+        let mut previous_write = false;
+
+        while let Some(operation) = operations.next() {
+            // Auto end is only set true on the last operation so that RESTART is used otherwise.
+            // This is required for combined write/read within one transaction.
+            let autoend = operations.peek().is_none();
+
+            // A NACK during a read can only happen in the address phase, because the
+            // data bytes are acknowledged by the master. For writes, it depends on the
+            // number of bytes already written.
+            let mut nack_source = match operation {
+                eh::i2c::Operation::Read(_) => eh::i2c::NoAcknowledgeSource::Address,
+                eh::i2c::Operation::Write(_) => eh::i2c::NoAcknowledgeSource::Unknown,
+            };
+
+            match operation {
+                eh::i2c::Operation::Read(buffer) => {
+                    let len = buffer.len();
+                    self.set_address(address, true, previous_write);
+                    self.set_transfer_size(len, autoend);
+                    regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+                    regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        if i > 0 && i.is_multiple_of(MAX_NBYTES) {
+                            self.wait_for_flags(ISR_TCR, nack_source)?;
+                            self.set_transfer_size(len - i, autoend);
+                        }
+                        self.wait_for_flags(ISR_RXNE, nack_source)?;
+                        *byte = regs.i2c_rxdr.read().rxdata().bits();
+                    }
+                    previous_write = false;
+                }
+                eh::i2c::Operation::Write(buffer) => {
+                    let len = buffer.len();
+                    self.set_address(address, false, false);
+                    self.set_transfer_size(len, autoend);
+                    regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+                    regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    for (i, byte) in buffer.iter().enumerate() {
+                        nack_source = write_nack_source(i);
+                        if i > 0 && i.is_multiple_of(MAX_NBYTES) {
+                            self.wait_for_flags(ISR_TCR, nack_source)?;
+                            self.set_transfer_size(len - i, autoend);
+                        }
+                        self.wait_for_flags(ISR_TXE, nack_source)?;
+                        regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
+                    }
+                    nack_source = write_nack_source(len);
+                    previous_write = true;
+                }
+            }
+
+            if autoend {
+                self.wait_for_flags(ISR_STOPF, nack_source)?;
+                regs.i2c_icr.write(|w| w.stopcf().set_bit());
+            } else {
+                self.wait_for_flags(ISR_TC, nack_source)?;
+            }
+        }
+
+        Ok(())
+    }
 
-        self.disable();
+    /// Waits until one of the ISR flags in `mask` is set, blocking.
+    ///
+    /// Returns an error if a NACK or a bus error is detected or the flag is not set
+    /// within the timeout. `nack_source` is reported for a NACK.
+    fn wait_for_flags(
+        &mut self,
+        mask: u32,
+        nack_source: eh::i2c::NoAcknowledgeSource,
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        let regs = R::registers();
+        let start = Instant::now();
 
-        let presc_const = match config.speed {
-            I2cSpeed::Standard => 4_000_000,
-            I2cSpeed::Fast => 8_000_000,
-            I2cSpeed::FastPlus => 8_000_000,
-        };
+        loop {
+            // ISR is only read once per iteration to keep the per-byte overhead low.
+            let isr = regs.i2c_isr.read().bits();
 
-        let presc_val = (R::clock_frequency() as u32 / presc_const).min(16);
+            if let Some(error) = self.take_error(isr, nack_source) {
+                return Err(error);
+            }
 
-        let scll_val = if presc_val == 16 {
-            (R::clock_frequency() as u32 / presc_val) / (2 * config.speed.hz())
-        } else {
-            presc_const / (2 * config.speed.hz())
-        };
+            if isr & mask != 0 {
+                return Ok(());
+            }
 
-        let sclh_val = match config.speed {
-            I2cSpeed::Standard => scll_val - 4,
-            I2cSpeed::Fast => scll_val * 4 / 10,
-            I2cSpeed::FastPlus => scll_val / 2,
-        };
+            if start.is_elapsed_millis(self.timeout) {
+                self.reset_state();
+                return Err(eh::i2c::ErrorKind::Other);
+            }
+        }
+    }
 
-        let presc = presc_val - 1;
-        let scll = scll_val - 1;
-        let sclh = sclh_val - 1;
+    /// Waits asynchronuously until one of the ISR flags in `mask` is set.
+    ///
+    /// Returns an error if a NACK or a bus error is detected. `nack_source` is reported
+    /// for a NACK.
+    async fn wait_for_flags_async(
+        &mut self,
+        mask: u32,
+        nack_source: eh::i2c::NoAcknowledgeSource,
+    ) -> Result<(), eh::i2c::ErrorKind> {
+        poll_fn(|cx| {
+            let regs = R::registers();
+            let isr = regs.i2c_isr.read().bits();
 
-        let sdadel = match config.speed {
-            I2cSpeed::Standard => 0x2,
-            I2cSpeed::Fast => 0x3,
-            I2cSpeed::FastPlus => 0x0,
-        };
+            if let Some(error) = self.take_error(isr, nack_source) {
+                return Poll::Ready(Err(error));
+            }
 
-        let scldel = match config.speed {
-            I2cSpeed::Standard => 0x4,
-            I2cSpeed::Fast => 0x3,
-            I2cSpeed::FastPlus => 0x1,
-        };
+            if isr & mask != 0 {
+                Poll::Ready(Ok(()))
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
 
-        assert!(presc <= 15);
-        assert!(scldel <= 15);
-        assert!(sdadel <= 15);
-        assert!(scll <= 255);
-        assert!(sclh <= 255);
+    /// Checks the ISR error flags in `isr` and returns the error after clearing its flag.
+    ///
+    /// A NACK is reported with `nack_source`, arbitration loss, bus error and
+    /// overrun/underrun are mapped to the matching `ErrorKind`.
+    fn take_error(
+        &mut self,
+        isr: u32,
+        nack_source: eh::i2c::NoAcknowledgeSource,
+    ) -> Option<eh::i2c::ErrorKind> {
+        if isr & ISR_ERRORS == 0 {
+            return None;
+        }
 
         let regs = R::registers();
 
-        unsafe {
-            regs.i2c_timingr.write(|w| {
-                w.presc()
-                    .bits(presc as u8)
-                    .scldel()
-                    .bits(scldel as u8)
-                    .sdadel()
-                    .bits(sdadel as u8)
-                    .sclh()
-                    .bits(sclh as u8)
-                    .scll()
-                    .bits(scll as u8)
-            });
+        if isr & ISR_NACKF != 0 {
+            // A STOP condition is generated automatically after a NACK in master mode.
+            let start = Instant::now();
+            while regs.i2c_isr.read().bits() & ISR_STOPF == 0 {
+                if start.is_elapsed_millis(self.timeout) {
+                    break;
+                }
+            }
+            regs.i2c_icr
+                .write(|w| w.nackcf().set_bit().stopcf().set_bit());
+
+            // Flush the transmit data register.
+            regs.i2c_isr.modify(|_, w| w.txe().set_bit());
+
+            return Some(eh::i2c::ErrorKind::NoAcknowledge(nack_source));
         }
 
-        regs.i2c_icr.write(|w| {
-            w.addrcf()
-                .set_bit()
-                .nackcf()
-                .set_bit()
-                .stopcf()
-                .set_bit()
-                .berrcf()
-                .set_bit()
-                .arlocf()
-                .set_bit()
-                .ovrcf()
-                .set_bit()
-                .peccf()
-                .set_bit()
-                .timoutcf()
-                .set_bit()
-                .alertcf()
-                .set_bit()
-        });
+        if isr & ISR_ARLO != 0 {
+            regs.i2c_icr.write(|w| w.arlocf().set_bit());
+            return Some(eh::i2c::ErrorKind::ArbitrationLoss);
+        }
 
-        self.enable();
-    }
+        if isr & ISR_BERR != 0 {
+            regs.i2c_icr.write(|w| w.berrcf().set_bit());
+            return Some(eh::i2c::ErrorKind::Bus);
+        }
 
-    /// Deinitializes the peripheral.
-    pub fn deinit(&mut self) {
-        self.disable();
-        R::disable_clock();
+        if isr & ISR_OVR != 0 {
+            regs.i2c_icr.write(|w| w.ovrcf().set_bit());
+            return Some(eh::i2c::ErrorKind::Overrun);
+        }
+
+        None
     }
 
-    /// Returns if a device responds at the specified address.
-    pub fn is_device_ready(&mut self, address: u8) -> bool {
+    /// Sets the slave address and transfer direction in CR2.
+    ///
+    /// For a 10-bit read following a write to the same slave within a transaction,
+    /// `head10r` makes the RESTART only send the header with the read direction
+    /// instead of the complete 10-bit address sequence.
+    fn set_address(&mut self, address: Address, read: bool, head10r: bool) {
         let regs = R::registers();
 
-        // Wait for any ongoing operation to be finished.
-        while regs.i2c_isr.read().busy().bit_is_set() {}
-
-        // Clear NACK and STOP flags.
-        regs.i2c_icr
-            .write(|w| w.nackcf().set_bit().stopcf().set_bit());
-
         unsafe {
             regs.i2c_cr2.modify(|_, w| {
                 w.sadd()
-                    .bits((address as u16) << 1)
-                    .nbytes()
-                    .bits(0)
+                    .bits(address.sadd())
+                    .add10()
+                    .bit(address.is_ten_bit())
+                    .head10r()
+                    .bit(head10r && address.is_ten_bit())
                     .rd_wrn()
-                    .clear_bit()
-                    .autoend()
-                    .set_bit()
-                    .start()
-                    .set_bit()
+                    .bit(read)
             });
         }
+    }
 
-        while regs.i2c_isr.read().stopf().bit_is_clear() {}
-
-        let nack = regs.i2c_isr.read().nackf().bit_is_set();
+    /// Sets NBYTES for the next chunk of a transfer with `remaining` bytes left.
+    ///
+    /// Transfers of more than 255 bytes are split into chunks using RELOAD. Auto end
+    /// is only applied to the last chunk, because it is ignored while RELOAD is set.
+    fn set_transfer_size(&mut self, remaining: usize, autoend: bool) {
+        let regs = R::registers();
+        let reload = remaining > MAX_NBYTES;
 
-        if nack {
-            regs.i2c_icr
-                .write(|w| w.nackcf().set_bit().stopcf().set_bit());
+        unsafe {
+            regs.i2c_cr2.modify(|_, w| {
+                w.nbytes()
+                    .bits(remaining.min(MAX_NBYTES) as u8)
+                    .reload()
+                    .bit(reload)
+                    .autoend()
+                    .bit(autoend && !reload)
+            });
         }
+    }
 
-        !nack
+    /// Resets the communication state by disabling and re-enabling the peripheral.
+    fn reset_state(&mut self) {
+        let regs = R::registers();
+        self.disable();
+        while regs.i2c_cr1.read().pe().bit_is_set() {}
+        self.enable();
     }
 
     /// Reads bytes from the slave asynchronuously.
     pub async fn read_async(
         &mut self,
-        address: u8,
+        address: impl Into<Address>,
         read: &mut [u8],
     ) -> Result<(), eh::i2c::ErrorKind> {
         self.transaction_async(address, &mut [eh::i2c::Operation::Read(read)])
             .await
     }
 
+    /// Reads bytes from the slave asynchronuously and returns the number of received bytes.
+    ///
+    /// On error, the number of valid bytes in the buffer is returned together with
+    /// the error.
+    pub async fn read_n_async(
+        &mut self,
+        address: impl Into<Address>,
+        read: &mut [u8],
+    ) -> Result<usize, (eh::i2c::ErrorKind, usize)> {
+        let regs = R::registers();
+
+        // Wait for any ongoing operation to be finished.
+        self.wait_while_busy_async().await;
+
+        let nack_source = eh::i2c::NoAcknowledgeSource::Address;
+        let len = read.len();
+        let mut count = 0;
+
+        self.set_address(address.into(), true, false);
+        self.set_transfer_size(len, true);
+        regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        for byte in read.iter_mut() {
+            if count > 0 && count.is_multiple_of(MAX_NBYTES) {
+                self.wait_for_flags_async(ISR_TCR, nack_source)
+                    .await
+                    .map_err(|error| (error, count))?;
+                self.set_transfer_size(len - count, true);
+            }
+            self.wait_for_flags_async(ISR_RXNE, nack_source)
+                .await
+                .map_err(|error| (error, count))?;
+            *byte = regs.i2c_rxdr.read().rxdata().bits();
+            count += 1;
+        }
+
+        self.wait_for_flags_async(ISR_STOPF, nack_source)
+            .await
+            .map_err(|error| (error, count))?;
+        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+
+        Ok(count)
+    }
+
     /// Writes bytes to the slave asynchronuously.
     pub async fn write_async(
         &mut self,
-        address: u8,
+        address: impl Into<Address>,
         write: &[u8],
     ) -> Result<(), eh::i2c::ErrorKind> {
         self.transaction_async(address, &mut [eh::i2c::Operation::Write(write)])
@@ -251,7 +1333,7 @@ where
     /// Writes a number of bytes to the slave, then reads some bytes back.
     pub async fn write_read_async(
         &mut self,
-        address: u8,
+        address: impl Into<Address>,
         write: &[u8],
         read: &mut [u8],
     ) -> Result<(), eh::i2c::ErrorKind> {
@@ -266,9 +1348,11 @@ where
     }
 
     /// Execute operations on the bus asynchronuously.
+    ///
+    /// Errors are reported the same way as in `transaction()`, except for timeouts.
     pub async fn transaction_async(
         &mut self,
-        address: u8,
+        address: impl Into<Address>,
         operations: &mut [eh::i2c::Operation<'_>],
     ) -> Result<(), eh::i2c::ErrorKind> {
         let regs = R::registers();
@@ -276,69 +1360,69 @@ where
         // Wait for any ongoing operation to be finished.
         self.wait_while_busy_async().await;
 
+        let address = address.into();
         let mut operations = operations.iter_mut().peekable();
+        let mut previous_write = false;
 
         while let Some(operation) = operations.next() {
             // Auto end is only set true on the last operation so that RESTART is used otherwise.
             // This is required for combined write/read within one transaction.
             let autoend = operations.peek().is_none();
 
+            // A NACK during a read can only happen in the address phase, because the
+            // data bytes are acknowledged by the master. For writes, it depends on the
+            // number of bytes already written.
+            let mut nack_source = match operation {
+                eh::i2c::Operation::Read(_) => eh::i2c::NoAcknowledgeSource::Address,
+                eh::i2c::Operation::Write(_) => eh::i2c::NoAcknowledgeSource::Unknown,
+            };
+
             match operation {
                 eh::i2c::Operation::Read(buffer) => {
-                    unsafe {
-                        // Set slave address, transfer size and flags.
-                        regs.i2c_cr2.modify(|_, w| {
-                            w.sadd()
-                                .bits((address as u16) << 1)
-                                .nbytes()
-                                .bits(buffer.len() as u8)
-                                .rd_wrn()
-                                .set_bit()
-                                .autoend()
-                                .bit(autoend)
-                                .start()
-                                .set_bit()
-                        });
-                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        for byte in buffer.iter_mut() {
-                            self.wait_for_receiver_not_empty_async().await;
-                            *byte = regs.i2c_rxdr.read().rxdata().bits();
-                        }
-                        if autoend {
-                            self.wait_for_stop_async().await;
-                            regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        } else {
-                            self.wait_for_transfer_complete_async().await;
+                    let len = buffer.len();
+                    self.set_address(address, true, previous_write);
+                    self.set_transfer_size(len, autoend);
+                    regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+                    regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    for (i, byte) in buffer.iter_mut().enumerate() {
+                        if i > 0 && i.is_multiple_of(MAX_NBYTES) {
+                            self.wait_for_flags_async(ISR_TCR, nack_source).await?;
+                            self.set_transfer_size(len - i, autoend);
                         }
+                        self.wait_for_flags_async(ISR_RXNE, nack_source).await?;
+                        *byte = regs.i2c_rxdr.read().rxdata().bits();
+                    }
+                    if autoend {
+                        self.wait_for_flags_async(ISR_STOPF, nack_source).await?;
+                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    } else {
+                        self.wait_for_flags_async(ISR_TC, nack_source).await?;
                     }
+                    previous_write = false;
                 }
                 eh::i2c::Operation::Write(buffer) => {
-                    unsafe {
-                        // Set slave address and transfer size.
-                        regs.i2c_cr2.modify(|_, w| {
-                            w.sadd()
-                                .bits((address as u16) << 1)
-                                .nbytes()
-                                .bits(buffer.len() as u8)
-                                .rd_wrn()
-                                .clear_bit()
-                                .autoend()
-                                .bit(autoend)
-                                .start()
-                                .set_bit()
-                        });
-                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        for byte in buffer.iter() {
-                            self.wait_for_transmitter_empty_async().await;
-                            regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
-                        }
-                        if autoend {
-                            self.wait_for_stop_async().await;
-                            regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        } else {
-                            self.wait_for_transfer_complete_async().await;
+                    let len = buffer.len();
+                    self.set_address(address, false, false);
+                    self.set_transfer_size(len, autoend);
+                    regs.i2c_cr2.modify(|_, w| w.start().set_bit());
+                    regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    for (i, byte) in buffer.iter().enumerate() {
+                        nack_source = write_nack_source(i);
+                        if i > 0 && i.is_multiple_of(MAX_NBYTES) {
+                            self.wait_for_flags_async(ISR_TCR, nack_source).await?;
+                            self.set_transfer_size(len - i, autoend);
                         }
+                        self.wait_for_flags_async(ISR_TXE, nack_source).await?;
+                        regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
+                    }
+                    nack_source = write_nack_source(len);
+                    if autoend {
+                        self.wait_for_flags_async(ISR_STOPF, nack_source).await?;
+                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
+                    } else {
+                        self.wait_for_flags_async(ISR_TC, nack_source).await?;
                     }
+                    previous_write = true;
                 }
             }
         }
@@ -428,12 +1512,76 @@ where
         .await
     }
 
+    /// Asynchronuously wait for transfer complete reload.
+    pub async fn wait_for_transfer_complete_reload_async(&self) {
+        poll_fn(|cx| {
+            let regs = R::registers();
+            if regs.i2c_isr.read().tcr().bit_is_clear() {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await
+    }
+
+    /// Returns if the peripheral was addressed as slave.
+    pub fn is_address_matched(&self) -> bool {
+        let regs = R::registers();
+        regs.i2c_isr.read().addr().bit_is_set()
+    }
+
+    /// Returns the 7-bit address that matched when addressed as slave.
+    ///
+    /// Only valid while `is_address_matched()` returns `true`.
+    pub fn matched_address(&self) -> u8 {
+        let regs = R::registers();
+        regs.i2c_isr.read().addcode().bits()
+    }
+
+    /// Clears the address matched flag, releasing the clock stretching.
+    pub fn clear_address_matched(&mut self) {
+        let regs = R::registers();
+        regs.i2c_icr.write(|w| w.addrcf().set_bit());
+    }
+
+    /// Enables the wakeup from Stop mode on address match.
+    ///
+    /// Only valid in slave mode and when the kernel clock is HSI, because all other
+    /// clocks are stopped in Stop mode. The digital noise filter must be disabled,
+    /// otherwise the setting has no effect.
+    pub fn enable_wakeup(&mut self) {
+        let regs = R::registers();
+        regs.i2c_cr1.modify(|_, w| w.wupen().set_bit());
+    }
+
+    /// Disables the wakeup from Stop mode.
+    pub fn disable_wakeup(&mut self) {
+        let regs = R::registers();
+        regs.i2c_cr1.modify(|_, w| w.wupen().clear_bit());
+    }
+
     /// Returns the register block.
     pub fn registers(&self) -> &'static RegisterBlock {
         R::registers()
     }
 }
 
+/// Returns the NACK source while waiting in a write after `written` bytes were put
+/// into the transmit data register.
+///
+/// The first byte is already written before the address is sent, it only leaves the
+/// data register after the address was acknowledged. So a NACK with more than one byte
+/// written must be for a data byte.
+fn write_nack_source(written: usize) -> eh::i2c::NoAcknowledgeSource {
+    match written {
+        0 => eh::i2c::NoAcknowledgeSource::Address,
+        1 => eh::i2c::NoAcknowledgeSource::Unknown,
+        _ => eh::i2c::NoAcknowledgeSource::Data,
+    }
+}
+
 // --------------------------- embedded-hal ---------------------------
 
 impl<R> eh::i2c::ErrorType for I2c<R>
@@ -452,79 +1600,20 @@ where
         address: u8,
         operations: &mut [eh::i2c::Operation<'_>],
     ) -> Result<(), Self::Error> {
-        let regs = R::registers();
-
-        // Wait for any ongoing operation to be finished.
-        while regs.i2c_isr.read().busy().bit_is_set() {}
-
-        let mut operations = operations.iter_mut().peekable();
-
-        while let Some(operation) = operations.next() {
-            // Auto end is only set true on the last operation so that RESTART is used otherwise.
-            // This is required for combined write/read within one transaction.
-            let autoend = operations.peek().is_none();
-
-            match operation {
-                eh::i2c::Operation::Read(buffer) => {
-                    unsafe {
-                        // Set slave address, transfer size and flags.
-                        regs.i2c_cr2.modify(|_, w| {
-                            w.sadd()
-                                .bits((address as u16) << 1)
-                                .nbytes()
-                                .bits(buffer.len() as u8)
-                                .rd_wrn()
-                                .set_bit()
-                                .autoend()
-                                .bit(autoend)
-                                .start()
-                                .set_bit()
-                        });
-                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        for byte in buffer.iter_mut() {
-                            while regs.i2c_isr.read().rxne().bit_is_clear() {}
-                            *byte = regs.i2c_rxdr.read().rxdata().bits();
-                        }
-                        if autoend {
-                            while regs.i2c_isr.read().stopf().bit_is_clear() {}
-                            regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        } else {
-                            while regs.i2c_isr.read().tc().bit_is_clear() {}
-                        }
-                    }
-                }
-                eh::i2c::Operation::Write(buffer) => {
-                    unsafe {
-                        // Set slave address and transfer size.
-                        regs.i2c_cr2.modify(|_, w| {
-                            w.sadd()
-                                .bits((address as u16) << 1)
-                                .nbytes()
-                                .bits(buffer.len() as u8)
-                                .rd_wrn()
-                                .clear_bit()
-                                .autoend()
-                                .bit(autoend)
-                                .start()
-                                .set_bit()
-                        });
-                        regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        for byte in buffer.iter() {
-                            while regs.i2c_isr.read().txe().bit_is_clear() {}
-                            regs.i2c_txdr.write(|w| w.txdata().bits(*byte));
-                        }
-                        if autoend {
-                            while regs.i2c_isr.read().stopf().bit_is_clear() {}
-                            regs.i2c_icr.write(|w| w.stopcf().set_bit());
-                        } else {
-                            while regs.i2c_isr.read().tc().bit_is_clear() {}
-                        }
-                    }
-                }
-            }
-        }
+        I2c::transaction(self, address, operations)
+    }
+}
 
-        Ok(())
+impl<R> embedded_hal_async::i2c::I2c for I2c<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [eh::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction_async(self, address, operations).await
     }
 }
 
@@ -541,8 +1630,17 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
+
+    /// Returns the DMA request input for the transmitter, if available.
+    fn tx_dma_request() -> Option<DmaRequestInput>;
+
+    /// Returns the DMA request input for the receiver, if available.
+    fn rx_dma_request() -> Option<DmaRequestInput>;
 }
 
 // ------------------------------- I2C1 -------------------------------
@@ -576,8 +1674,22 @@ impl Instance for I2C1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.i2c1rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.i2c1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::i2c12_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c1Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c1Rx)
     }
 }
 
@@ -612,8 +1724,22 @@ impl Instance for I2C2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.i2c2rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.i2c2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::i2c12_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c2Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c2Rx)
     }
 }
 
@@ -648,8 +1774,22 @@ impl Instance for I2C3 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.i2c3rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.i2c3rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::i2c35_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c3Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c3Rx)
     }
 }
 
@@ -685,8 +1825,24 @@ impl Instance for I2C4 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb5rstsetr.write(|w| w.i2c4rst().set_bit());
+        rcc.rcc_apb5rstclrr.write(|w| w.i2c4rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk5_frequency()
+        rcc::i2c46_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        // I2C4 requests are only connected to the MDMA.
+        None
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        // I2C4 requests are only connected to the MDMA.
+        None
     }
 }
 
@@ -721,8 +1877,22 @@ impl Instance for I2C5 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.i2c5rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.i2c5rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::i2c35_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c5Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::I2c5Rx)
     }
 }
 
@@ -757,7 +1927,23 @@ impl Instance for I2C6 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb5rstsetr.write(|w| w.i2c6rst().set_bit());
+        rcc.rcc_apb5rstclrr.write(|w| w.i2c6rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk5_frequency()
+        rcc::i2c46_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        // I2C6 requests are only connected to the MDMA.
+        None
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        // I2C6 requests are only connected to the MDMA.
+        None
     }
 }
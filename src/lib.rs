@@ -17,13 +17,16 @@ cfg_if! {
 pub mod bitworker;
 pub mod dma;
 pub mod dmamux;
+pub mod exti;
 pub mod gpio;
 pub mod i2c;
 pub mod ltdc;
+pub mod pwr;
 pub mod rcc;
 pub mod rng;
 pub mod sai;
 pub mod sdmmc;
+pub mod softspi;
 pub mod spi;
 pub mod stgen;
 pub mod time;
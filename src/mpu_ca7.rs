@@ -71,7 +71,7 @@ pub fn is_mpu1_initialized() -> bool {
 /// - Enables the MMU of MPU0 with a translation table.
 /// - Initializes the IRQs and GIC for MPU0.
 fn init_mpu0(config: HalConfig) {
-    cortex_a7::enable_scu();
+    cortex_a7::enable_smp();
     unsecure_peripherals();
 
     critical_section_impl::init();
@@ -95,9 +95,12 @@ fn init_mpu0(config: HalConfig) {
 ///
 /// This function is called from `init` for MPU1.
 /// It performs the following tasks:
+/// - Adds MPU1 to the cache coherency of the SCU.
 /// - Enables the MMU of MPU1 with a translation table.
 /// - Initializes the GIC for MPU1.
 fn init_mpu1(config: HalConfig) {
+    cortex_a7::enable_smp();
+
     unsafe {
         cortex_a7::memory::mmu::init_translation_table(
             &mut *&raw mut MMU_TRANSLATION_TABLES.mpu1,
@@ -473,6 +473,10 @@ pub fn set_irq_handler(irq_handler: Option<IrqHandler>) {
 extern "C" fn irq_handler() {
     let irqn = gic::acknowledge_pending();
 
+    if irqn == Irqn::SecurePhysicalTimer as u32 {
+        crate::stgen::on_tick_interrupt();
+    }
+
     unsafe {
         if let Some(irq_handler) = IRQ_HANDLER {
             if let Ok(irqn) = Irqn::try_from(irqn) {
@@ -0,0 +1,23 @@
+//! Power control.
+
+use crate::pac;
+
+/// Disables the write protection of the backup domain.
+///
+/// This is required before modifying the RTC, the backup registers and the
+/// backup domain control register of the RCC (LSE, RTC clock).
+pub fn disable_backup_domain_write_protection() {
+    unsafe {
+        let pwr = &(*pac::PWR::ptr());
+        pwr.pwr_cr1.modify(|_, w| w.dbp().set_bit());
+        while pwr.pwr_cr1.read().dbp().bit_is_clear() {}
+    }
+}
+
+/// Enables the write protection of the backup domain.
+pub fn enable_backup_domain_write_protection() {
+    unsafe {
+        let pwr = &(*pac::PWR::ptr());
+        pwr.pwr_cr1.modify(|_, w| w.dbp().clear_bit());
+    }
+}
@@ -0,0 +1,168 @@
+//! I2C kernel clock selection.
+
+use super::{csi, hsi_frequency, pclk1_frequency, pclk5_frequency};
+use super::{pll3_q_frequency, pll4_r_frequency};
+use crate::pac;
+
+// ----------------------------- I2C1/2 ------------------------------
+
+/// Sets the I2C1/I2C2 kernel clock source.
+pub fn set_i2c12_source(source: I2cSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_i2c12ckselr
+            .modify(|_, w| w.i2c12src().bits(source.into()));
+    }
+}
+
+/// Returns the I2C1/I2C2 kernel clock source.
+pub fn i2c12_source() -> I2cSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        I2cSource::try_from(rcc.rcc_i2c12ckselr.read().i2c12src().bits()).unwrap()
+    }
+}
+
+/// Returns the I2C1/I2C2 kernel clock frequency in Hz.
+pub fn i2c12_kernel_frequency() -> f32 {
+    match i2c12_source() {
+        I2cSource::Pclk1 => pclk1_frequency(),
+        I2cSource::Pll4R => pll4_r_frequency(),
+        I2cSource::Hsi => hsi_frequency() as f32,
+        I2cSource::Csi => csi::Csi::new().frequency() as f32,
+    }
+}
+
+// ----------------------------- I2C3/5 ------------------------------
+
+/// Sets the I2C3/I2C5 kernel clock source.
+pub fn set_i2c35_source(source: I2cSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_i2c35ckselr
+            .modify(|_, w| w.i2c35src().bits(source.into()));
+    }
+}
+
+/// Returns the I2C3/I2C5 kernel clock source.
+pub fn i2c35_source() -> I2cSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        I2cSource::try_from(rcc.rcc_i2c35ckselr.read().i2c35src().bits()).unwrap()
+    }
+}
+
+/// Returns the I2C3/I2C5 kernel clock frequency in Hz.
+pub fn i2c35_kernel_frequency() -> f32 {
+    match i2c35_source() {
+        I2cSource::Pclk1 => pclk1_frequency(),
+        I2cSource::Pll4R => pll4_r_frequency(),
+        I2cSource::Hsi => hsi_frequency() as f32,
+        I2cSource::Csi => csi::Csi::new().frequency() as f32,
+    }
+}
+
+/// I2C1/2/3/5 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum I2cSource {
+    /// PCLK1 clock.
+    Pclk1,
+    /// PLL4 R clock.
+    Pll4R,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+}
+
+impl TryFrom<u8> for I2cSource {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(I2cSource::Pclk1),
+            0b001 => Ok(I2cSource::Pll4R),
+            0b010 => Ok(I2cSource::Hsi),
+            0b011 => Ok(I2cSource::Csi),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<I2cSource> for u8 {
+    fn from(value: I2cSource) -> Self {
+        match value {
+            I2cSource::Pclk1 => 0b000,
+            I2cSource::Pll4R => 0b001,
+            I2cSource::Hsi => 0b010,
+            I2cSource::Csi => 0b011,
+        }
+    }
+}
+
+// ----------------------------- I2C4/6 ------------------------------
+
+/// Sets the I2C4/I2C6 kernel clock source.
+pub fn set_i2c46_source(source: I2c46Source) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_i2c46ckselr
+            .modify(|_, w| w.i2c46src().bits(source.into()));
+    }
+}
+
+/// Returns the I2C4/I2C6 kernel clock source.
+pub fn i2c46_source() -> I2c46Source {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        I2c46Source::try_from(rcc.rcc_i2c46ckselr.read().i2c46src().bits()).unwrap()
+    }
+}
+
+/// Returns the I2C4/I2C6 kernel clock frequency in Hz.
+pub fn i2c46_kernel_frequency() -> f32 {
+    match i2c46_source() {
+        I2c46Source::Pclk5 => pclk5_frequency(),
+        I2c46Source::Pll3Q => pll3_q_frequency(),
+        I2c46Source::Hsi => hsi_frequency() as f32,
+        I2c46Source::Csi => csi::Csi::new().frequency() as f32,
+    }
+}
+
+/// I2C4/6 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum I2c46Source {
+    /// PCLK5 clock.
+    Pclk5,
+    /// PLL3 Q clock.
+    Pll3Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+}
+
+impl TryFrom<u8> for I2c46Source {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(I2c46Source::Pclk5),
+            0b001 => Ok(I2c46Source::Pll3Q),
+            0b010 => Ok(I2c46Source::Hsi),
+            0b011 => Ok(I2c46Source::Csi),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<I2c46Source> for u8 {
+    fn from(value: I2c46Source) -> Self {
+        match value {
+            I2c46Source::Pclk5 => 0b000,
+            I2c46Source::Pll3Q => 0b001,
+            I2c46Source::Hsi => 0b010,
+            I2c46Source::Csi => 0b011,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! LSE oscillator.
+
+use crate::pac;
+
+/// Frequency of the LSE oscillator in Hz.
+const LSE_FREQUENCY: u32 = 32768;
+
+/// Enables the LSE oscillator and waits until it is ready.
+/// - `bypass`: Use an external clock signal instead of a crystal.
+///
+/// The backup domain write protection must be disabled before, see `pwr`.
+/// Startup of a crystal can take up to 2 seconds, so this function may block that long.
+pub fn enable_lse(bypass: bool) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+
+        // The bypass setting can only be changed while the oscillator is off.
+        rcc.rcc_bdcr.modify(|_, w| w.lseon().clear_bit());
+        while rcc.rcc_bdcr.read().lserdy().bit_is_set() {}
+
+        rcc.rcc_bdcr.modify(|_, w| w.lsebyp().bit(bypass));
+        rcc.rcc_bdcr.modify(|_, w| w.lseon().set_bit());
+    }
+
+    while !is_lse_ready() {}
+}
+
+/// Disables the LSE oscillator.
+///
+/// The backup domain write protection must be disabled before, see `pwr`.
+pub fn disable_lse() {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_bdcr.modify(|_, w| w.lseon().clear_bit());
+    }
+}
+
+/// Returns if the LSE oscillator is ready.
+pub fn is_lse_ready() -> bool {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_bdcr.read().lserdy().bit_is_set()
+    }
+}
+
+/// Returns the frequency of the LSE oscillator in Hz.
+pub fn lse_frequency() -> u32 {
+    LSE_FREQUENCY
+}
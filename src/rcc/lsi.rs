@@ -0,0 +1,39 @@
+//! LSI oscillator.
+
+use crate::pac;
+
+/// Typical frequency of the LSI oscillator in Hz.
+const LSI_FREQUENCY: u32 = 32000;
+
+/// Enables the LSI oscillator and waits until it is ready.
+pub fn enable_lsi() {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_rdlsicr.modify(|_, w| w.lsion().set_bit());
+    }
+
+    while !is_lsi_ready() {}
+}
+
+/// Disables the LSI oscillator.
+pub fn disable_lsi() {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_rdlsicr.modify(|_, w| w.lsion().clear_bit());
+    }
+}
+
+/// Returns if the LSI oscillator is ready.
+pub fn is_lsi_ready() -> bool {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_rdlsicr.read().lsirdy().bit_is_set()
+    }
+}
+
+/// Returns the typical frequency of the LSI oscillator in Hz.
+///
+/// The actual frequency varies with temperature and voltage.
+pub fn lsi_frequency() -> u32 {
+    LSI_FREQUENCY
+}
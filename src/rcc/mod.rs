@@ -3,13 +3,25 @@
 pub mod csi;
 mod hse;
 mod hsi;
+mod i2c;
+mod lse;
+mod lsi;
 mod pll;
+mod rtc;
+mod spi;
+mod usart;
 
 use crate::pac;
 
 pub use hse::*;
 pub use hsi::*;
+pub use i2c::*;
+pub use lse::*;
+pub use lsi::*;
 pub use pll::*;
+pub use rtc::*;
+pub use spi::*;
+pub use usart::*;
 
 // ------------------------------- MPU -------------------------------
 
@@ -0,0 +1,66 @@
+//! RTC clock selection.
+
+use crate::pac;
+
+/// Sets the RTC clock source and enables the RTC clock.
+///
+/// The backup domain write protection must be disabled before, see `pwr`.
+/// The source can only be changed once after a backup domain reset and the selected
+/// oscillator must be enabled and ready.
+pub fn set_rtc_source(source: RtcSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_bdcr.modify(|_, w| {
+            w.rtcsrc()
+                .bits(source.into())
+                .rtccken()
+                .bit(source != RtcSource::Disabled)
+        });
+    }
+}
+
+/// Returns the RTC clock source.
+pub fn rtc_source() -> RtcSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        RtcSource::try_from(rcc.rcc_bdcr.read().rtcsrc().bits()).unwrap()
+    }
+}
+
+/// RTC clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RtcSource {
+    /// No clock.
+    Disabled,
+    /// LSE oscillator.
+    Lse,
+    /// LSI oscillator.
+    Lsi,
+    /// HSE oscillator divided by RTCDIV.
+    Hse,
+}
+
+impl TryFrom<u8> for RtcSource {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b00 => Ok(RtcSource::Disabled),
+            0b01 => Ok(RtcSource::Lse),
+            0b10 => Ok(RtcSource::Lsi),
+            0b11 => Ok(RtcSource::Hse),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<RtcSource> for u8 {
+    fn from(value: RtcSource) -> Self {
+        match value {
+            RtcSource::Disabled => 0b00,
+            RtcSource::Lse => 0b01,
+            RtcSource::Lsi => 0b10,
+            RtcSource::Hse => 0b11,
+        }
+    }
+}
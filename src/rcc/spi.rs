@@ -0,0 +1,262 @@
+//! SPI kernel clock selection.
+
+use super::{
+    csi, hse_frequency, hsi_frequency, pclk2_frequency, pclk5_frequency, per_ck_frequency,
+};
+use super::{pll3_q_frequency, pll3_r_frequency, pll4_p_frequency, pll4_q_frequency};
+use crate::pac;
+
+// ----------------------------- SPI1 --------------------------------
+
+/// Sets the SPI1 kernel clock source.
+pub fn set_spi1_source(source: SpiSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_spi2s1ckselr
+            .modify(|_, w| w.spi1src().bits(source.into()));
+    }
+}
+
+/// Returns the SPI1 kernel clock source.
+pub fn spi1_source() -> SpiSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        SpiSource::try_from(rcc.rcc_spi2s1ckselr.read().spi1src().bits()).unwrap()
+    }
+}
+
+/// Returns the SPI1 kernel clock frequency in Hz.
+///
+/// The frequency of the external I2S_CKIN clock is unknown, so 0 is returned for it.
+pub fn spi1_kernel_frequency() -> f32 {
+    match spi1_source() {
+        SpiSource::Pll4P => pll4_p_frequency(),
+        SpiSource::Pll3Q => pll3_q_frequency(),
+        SpiSource::I2sCkin => 0.0,
+        SpiSource::Per => per_ck_frequency(),
+        SpiSource::Pll3R => pll3_r_frequency(),
+    }
+}
+
+// ----------------------------- SPI2/3 ------------------------------
+
+/// Sets the SPI2/SPI3 kernel clock source.
+pub fn set_spi23_source(source: SpiSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_spi2s23ckselr
+            .modify(|_, w| w.spi23src().bits(source.into()));
+    }
+}
+
+/// Returns the SPI2/SPI3 kernel clock source.
+pub fn spi23_source() -> SpiSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        SpiSource::try_from(rcc.rcc_spi2s23ckselr.read().spi23src().bits()).unwrap()
+    }
+}
+
+/// Returns the SPI2/SPI3 kernel clock frequency in Hz.
+///
+/// The frequency of the external I2S_CKIN clock is unknown, so 0 is returned for it.
+pub fn spi23_kernel_frequency() -> f32 {
+    match spi23_source() {
+        SpiSource::Pll4P => pll4_p_frequency(),
+        SpiSource::Pll3Q => pll3_q_frequency(),
+        SpiSource::I2sCkin => 0.0,
+        SpiSource::Per => per_ck_frequency(),
+        SpiSource::Pll3R => pll3_r_frequency(),
+    }
+}
+
+/// SPI1/2/3 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SpiSource {
+    /// PLL4 P clock.
+    Pll4P,
+    /// PLL3 Q clock.
+    Pll3Q,
+    /// External I2S_CKIN clock.
+    I2sCkin,
+    /// PER clock.
+    Per,
+    /// PLL3 R clock.
+    Pll3R,
+}
+
+impl TryFrom<u8> for SpiSource {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(SpiSource::Pll4P),
+            0b001 => Ok(SpiSource::Pll3Q),
+            0b010 => Ok(SpiSource::I2sCkin),
+            0b011 => Ok(SpiSource::Per),
+            0b100 => Ok(SpiSource::Pll3R),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<SpiSource> for u8 {
+    fn from(value: SpiSource) -> Self {
+        match value {
+            SpiSource::Pll4P => 0b000,
+            SpiSource::Pll3Q => 0b001,
+            SpiSource::I2sCkin => 0b010,
+            SpiSource::Per => 0b011,
+            SpiSource::Pll3R => 0b100,
+        }
+    }
+}
+
+// ----------------------------- SPI4/5 ------------------------------
+
+/// Sets the SPI4/SPI5 kernel clock source.
+pub fn set_spi45_source(source: Spi45Source) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_spi45ckselr
+            .modify(|_, w| w.spi45src().bits(source.into()));
+    }
+}
+
+/// Returns the SPI4/SPI5 kernel clock source.
+pub fn spi45_source() -> Spi45Source {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        Spi45Source::try_from(rcc.rcc_spi45ckselr.read().spi45src().bits()).unwrap()
+    }
+}
+
+/// Returns the SPI4/SPI5 kernel clock frequency in Hz.
+pub fn spi45_kernel_frequency() -> f32 {
+    match spi45_source() {
+        Spi45Source::Pclk2 => pclk2_frequency(),
+        Spi45Source::Pll4Q => pll4_q_frequency(),
+        Spi45Source::Hsi => hsi_frequency() as f32,
+        Spi45Source::Csi => csi::Csi::new().frequency() as f32,
+        Spi45Source::Hse => hse_frequency() as f32,
+    }
+}
+
+/// SPI4/5 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Spi45Source {
+    /// PCLK2 clock.
+    Pclk2,
+    /// PLL4 Q clock.
+    Pll4Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+    /// HSE kernel clock.
+    Hse,
+}
+
+impl TryFrom<u8> for Spi45Source {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(Spi45Source::Pclk2),
+            0b001 => Ok(Spi45Source::Pll4Q),
+            0b010 => Ok(Spi45Source::Hsi),
+            0b011 => Ok(Spi45Source::Csi),
+            0b100 => Ok(Spi45Source::Hse),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<Spi45Source> for u8 {
+    fn from(value: Spi45Source) -> Self {
+        match value {
+            Spi45Source::Pclk2 => 0b000,
+            Spi45Source::Pll4Q => 0b001,
+            Spi45Source::Hsi => 0b010,
+            Spi45Source::Csi => 0b011,
+            Spi45Source::Hse => 0b100,
+        }
+    }
+}
+
+// ----------------------------- SPI6 --------------------------------
+
+/// Sets the SPI6 kernel clock source.
+pub fn set_spi6_source(source: Spi6Source) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_spi6ckselr
+            .modify(|_, w| w.spi6src().bits(source.into()));
+    }
+}
+
+/// Returns the SPI6 kernel clock source.
+pub fn spi6_source() -> Spi6Source {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        Spi6Source::try_from(rcc.rcc_spi6ckselr.read().spi6src().bits()).unwrap()
+    }
+}
+
+/// Returns the SPI6 kernel clock frequency in Hz.
+pub fn spi6_kernel_frequency() -> f32 {
+    match spi6_source() {
+        Spi6Source::Pclk5 => pclk5_frequency(),
+        Spi6Source::Pll4Q => pll4_q_frequency(),
+        Spi6Source::Hsi => hsi_frequency() as f32,
+        Spi6Source::Csi => csi::Csi::new().frequency() as f32,
+        Spi6Source::Hse => hse_frequency() as f32,
+        Spi6Source::Pll3Q => pll3_q_frequency(),
+    }
+}
+
+/// SPI6 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Spi6Source {
+    /// PCLK5 clock.
+    Pclk5,
+    /// PLL4 Q clock.
+    Pll4Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+    /// HSE kernel clock.
+    Hse,
+    /// PLL3 Q clock.
+    Pll3Q,
+}
+
+impl TryFrom<u8> for Spi6Source {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(Spi6Source::Pclk5),
+            0b001 => Ok(Spi6Source::Pll4Q),
+            0b010 => Ok(Spi6Source::Hsi),
+            0b011 => Ok(Spi6Source::Csi),
+            0b100 => Ok(Spi6Source::Hse),
+            0b101 => Ok(Spi6Source::Pll3Q),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<Spi6Source> for u8 {
+    fn from(value: Spi6Source) -> Self {
+        match value {
+            Spi6Source::Pclk5 => 0b000,
+            Spi6Source::Pll4Q => 0b001,
+            Spi6Source::Hsi => 0b010,
+            Spi6Source::Csi => 0b011,
+            Spi6Source::Hse => 0b100,
+            Spi6Source::Pll3Q => 0b101,
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! USART kernel clock selection.
+
+use super::{csi, hse_frequency, hsi_frequency, pclk1_frequency, pclk2_frequency, pclk5_frequency};
+use super::{pll3_q_frequency, pll4_q_frequency};
+use crate::pac;
+
+// ----------------------------- USART1 ------------------------------
+
+/// Sets the USART1 kernel clock source.
+pub fn set_usart1_source(source: Usart1Source) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_uart1ckselr
+            .modify(|_, w| w.uart1src().bits(source.into()));
+    }
+}
+
+/// Returns the USART1 kernel clock source.
+pub fn usart1_source() -> Usart1Source {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        Usart1Source::try_from(rcc.rcc_uart1ckselr.read().uart1src().bits()).unwrap()
+    }
+}
+
+/// Returns the USART1 kernel clock frequency in Hz.
+pub fn usart1_kernel_frequency() -> f32 {
+    match usart1_source() {
+        Usart1Source::Pclk5 => pclk5_frequency(),
+        Usart1Source::Pll3Q => pll3_q_frequency(),
+        Usart1Source::Hsi => hsi_frequency() as f32,
+        Usart1Source::Csi => csi::Csi::new().frequency() as f32,
+        Usart1Source::Pll4Q => pll4_q_frequency(),
+        Usart1Source::Hse => hse_frequency() as f32,
+    }
+}
+
+/// USART1 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Usart1Source {
+    /// PCLK5 clock.
+    Pclk5,
+    /// PLL3 Q clock.
+    Pll3Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+    /// PLL4 Q clock.
+    Pll4Q,
+    /// HSE kernel clock.
+    Hse,
+}
+
+impl TryFrom<u8> for Usart1Source {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(Usart1Source::Pclk5),
+            0b001 => Ok(Usart1Source::Pll3Q),
+            0b010 => Ok(Usart1Source::Hsi),
+            0b011 => Ok(Usart1Source::Csi),
+            0b100 => Ok(Usart1Source::Pll4Q),
+            0b101 => Ok(Usart1Source::Hse),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<Usart1Source> for u8 {
+    fn from(value: Usart1Source) -> Self {
+        match value {
+            Usart1Source::Pclk5 => 0b000,
+            Usart1Source::Pll3Q => 0b001,
+            Usart1Source::Hsi => 0b010,
+            Usart1Source::Csi => 0b011,
+            Usart1Source::Pll4Q => 0b100,
+            Usart1Source::Hse => 0b101,
+        }
+    }
+}
+
+// ----------------------------- UART2/4 -----------------------------
+
+/// Sets the USART2/UART4 kernel clock source.
+pub fn set_uart24_source(source: UartSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_uart24ckselr
+            .modify(|_, w| w.uart24src().bits(source.into()));
+    }
+}
+
+/// Returns the USART2/UART4 kernel clock source.
+pub fn uart24_source() -> UartSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        UartSource::try_from(rcc.rcc_uart24ckselr.read().uart24src().bits()).unwrap()
+    }
+}
+
+/// Returns the USART2/UART4 kernel clock frequency in Hz.
+pub fn uart24_kernel_frequency() -> f32 {
+    match uart24_source() {
+        UartSource::Pclk1 => pclk1_frequency(),
+        UartSource::Pll4Q => pll4_q_frequency(),
+        UartSource::Hsi => hsi_frequency() as f32,
+        UartSource::Csi => csi::Csi::new().frequency() as f32,
+        UartSource::Hse => hse_frequency() as f32,
+    }
+}
+
+// ----------------------------- UART3/5 -----------------------------
+
+/// Sets the USART3/UART5 kernel clock source.
+pub fn set_uart35_source(source: UartSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_uart35ckselr
+            .modify(|_, w| w.uart35src().bits(source.into()));
+    }
+}
+
+/// Returns the USART3/UART5 kernel clock source.
+pub fn uart35_source() -> UartSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        UartSource::try_from(rcc.rcc_uart35ckselr.read().uart35src().bits()).unwrap()
+    }
+}
+
+/// Returns the USART3/UART5 kernel clock frequency in Hz.
+pub fn uart35_kernel_frequency() -> f32 {
+    match uart35_source() {
+        UartSource::Pclk1 => pclk1_frequency(),
+        UartSource::Pll4Q => pll4_q_frequency(),
+        UartSource::Hsi => hsi_frequency() as f32,
+        UartSource::Csi => csi::Csi::new().frequency() as f32,
+        UartSource::Hse => hse_frequency() as f32,
+    }
+}
+
+// ----------------------------- UART7/8 -----------------------------
+
+/// Sets the UART7/UART8 kernel clock source.
+pub fn set_uart78_source(source: UartSource) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_uart78ckselr
+            .modify(|_, w| w.uart78src().bits(source.into()));
+    }
+}
+
+/// Returns the UART7/UART8 kernel clock source.
+pub fn uart78_source() -> UartSource {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        UartSource::try_from(rcc.rcc_uart78ckselr.read().uart78src().bits()).unwrap()
+    }
+}
+
+/// Returns the UART7/UART8 kernel clock frequency in Hz.
+pub fn uart78_kernel_frequency() -> f32 {
+    match uart78_source() {
+        UartSource::Pclk1 => pclk1_frequency(),
+        UartSource::Pll4Q => pll4_q_frequency(),
+        UartSource::Hsi => hsi_frequency() as f32,
+        UartSource::Csi => csi::Csi::new().frequency() as f32,
+        UartSource::Hse => hse_frequency() as f32,
+    }
+}
+
+/// USART2/3, UART4/5/7/8 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UartSource {
+    /// PCLK1 clock.
+    Pclk1,
+    /// PLL4 Q clock.
+    Pll4Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+    /// HSE kernel clock.
+    Hse,
+}
+
+impl TryFrom<u8> for UartSource {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(UartSource::Pclk1),
+            0b001 => Ok(UartSource::Pll4Q),
+            0b010 => Ok(UartSource::Hsi),
+            0b011 => Ok(UartSource::Csi),
+            0b100 => Ok(UartSource::Hse),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<UartSource> for u8 {
+    fn from(value: UartSource) -> Self {
+        match value {
+            UartSource::Pclk1 => 0b000,
+            UartSource::Pll4Q => 0b001,
+            UartSource::Hsi => 0b010,
+            UartSource::Csi => 0b011,
+            UartSource::Hse => 0b100,
+        }
+    }
+}
+
+// ----------------------------- USART6 ------------------------------
+
+/// Sets the USART6 kernel clock source.
+pub fn set_usart6_source(source: Usart6Source) {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        rcc.rcc_uart6ckselr
+            .modify(|_, w| w.uart6src().bits(source.into()));
+    }
+}
+
+/// Returns the USART6 kernel clock source.
+pub fn usart6_source() -> Usart6Source {
+    unsafe {
+        let rcc = &(*pac::RCC::ptr());
+        Usart6Source::try_from(rcc.rcc_uart6ckselr.read().uart6src().bits()).unwrap()
+    }
+}
+
+/// Returns the USART6 kernel clock frequency in Hz.
+pub fn usart6_kernel_frequency() -> f32 {
+    match usart6_source() {
+        Usart6Source::Pclk2 => pclk2_frequency(),
+        Usart6Source::Pll4Q => pll4_q_frequency(),
+        Usart6Source::Hsi => hsi_frequency() as f32,
+        Usart6Source::Csi => csi::Csi::new().frequency() as f32,
+        Usart6Source::Hse => hse_frequency() as f32,
+    }
+}
+
+/// USART6 kernel clock source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Usart6Source {
+    /// PCLK2 clock.
+    Pclk2,
+    /// PLL4 Q clock.
+    Pll4Q,
+    /// HSI kernel clock.
+    Hsi,
+    /// CSI kernel clock.
+    Csi,
+    /// HSE kernel clock.
+    Hse,
+}
+
+impl TryFrom<u8> for Usart6Source {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(Usart6Source::Pclk2),
+            0b001 => Ok(Usart6Source::Pll4Q),
+            0b010 => Ok(Usart6Source::Hsi),
+            0b011 => Ok(Usart6Source::Csi),
+            0b100 => Ok(Usart6Source::Hse),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl From<Usart6Source> for u8 {
+    fn from(value: Usart6Source) -> Self {
+        match value {
+            Usart6Source::Pclk2 => 0b000,
+            Usart6Source::Pll4Q => 0b001,
+            Usart6Source::Hsi => 0b010,
+            Usart6Source::Csi => 0b011,
+            Usart6Source::Hse => 0b100,
+        }
+    }
+}
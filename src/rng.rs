@@ -26,6 +26,71 @@ pub type Rng1 = Rng<RNG1>;
 /// Type alias for RNG2.
 pub type Rng2 = Rng<RNG2>;
 
+// -------------------------- Entropy pool ----------------------------
+
+/// Ring buffer for random values collected in the interrupt handler.
+#[derive(Debug)]
+pub struct EntropyPool<const N: usize> {
+    /// Stored values.
+    data: [u32; N],
+    /// Index of the oldest value.
+    read_index: usize,
+    /// Number of stored values.
+    count: usize,
+}
+
+impl<const N: usize> Default for EntropyPool<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EntropyPool<N> {
+    /// Returns an empty pool.
+    pub const fn new() -> Self {
+        Self {
+            data: [0; N],
+            read_index: 0,
+            count: 0,
+        }
+    }
+
+    /// Returns the number of available values.
+    pub fn available(&self) -> usize {
+        self.count
+    }
+
+    /// Returns if no more values can be stored.
+    pub fn is_full(&self) -> bool {
+        self.count == N
+    }
+
+    /// Takes the oldest value out of the pool.
+    pub fn pop(&mut self) -> Option<u32> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let value = self.data[self.read_index];
+        self.read_index = (self.read_index + 1) % N;
+        self.count -= 1;
+
+        Some(value)
+    }
+
+    /// Stores a value, returns `false` if the pool is full.
+    fn push(&mut self, value: u32) -> bool {
+        if self.is_full() {
+            return false;
+        }
+
+        self.data[(self.read_index + self.count) % N] = value;
+        self.count += 1;
+
+        true
+    }
+}
+
 // ------------------------- Implementation ---------------------------
 
 impl<R> Rng<R>
@@ -50,16 +115,74 @@ where
     /// Deinitializes the peripheral.
     pub fn deinit(&mut self) {
         self.disable();
+        R::reset();
         R::disable_clock();
     }
 
     /// Returns a generated value.
     pub fn value(&self) -> u32 {
-        while !self.is_value_ready() {}
+        loop {
+            self.recover_from_errors();
+            if self.is_value_ready() {
+                break;
+            }
+        }
         let regs = R::registers();
         regs.rng_dr.read().bits()
     }
 
+    /// Returns a value from the pool or a generated value if the pool is empty.
+    pub fn pooled_value<const N: usize>(&mut self, pool: &mut EntropyPool<N>) -> u32 {
+        pool.pop().unwrap_or_else(|| self.value())
+    }
+
+    /// Enables the interrupt for data ready and error conditions.
+    pub fn enable_interrupt(&mut self) {
+        let regs = R::registers();
+        regs.rng_cr.modify(|_, w| w.ie().set_bit());
+    }
+
+    /// Disables the interrupt.
+    pub fn disable_interrupt(&mut self) {
+        let regs = R::registers();
+        regs.rng_cr.modify(|_, w| w.ie().clear_bit());
+    }
+
+    /// Handles the interrupt by moving generated values into a pool.
+    ///
+    /// This function must be called from the interrupt handler. Access to the pool from
+    /// other contexts must be protected, e.g. with a critical section.
+    /// When the pool is full, the interrupt is disabled and must be enabled again after
+    /// values were taken from the pool.
+    pub fn on_interrupt<const N: usize>(&mut self, pool: &mut EntropyPool<N>) {
+        self.recover_from_errors();
+
+        let regs = R::registers();
+        while self.is_value_ready() {
+            if pool.is_full() {
+                self.disable_interrupt();
+                break;
+            }
+            pool.push(regs.rng_dr.read().bits());
+        }
+    }
+
+    /// Clears error flags and restarts the generator on a seed error.
+    fn recover_from_errors(&self) {
+        let regs = R::registers();
+        let sr = regs.rng_sr.read();
+
+        if sr.seis().bit_is_set() || sr.ceis().bit_is_set() {
+            regs.rng_sr
+                .modify(|_, w| w.seis().clear_bit().ceis().clear_bit());
+
+            if sr.secs().bit_is_set() {
+                regs.rng_cr.modify(|_, w| w.rngen().clear_bit());
+                regs.rng_cr.modify(|_, w| w.rngen().set_bit());
+            }
+        }
+    }
+
     /// Enables the peripheral.
     pub fn enable(&mut self) {
         let regs = R::registers();
@@ -103,6 +226,9 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
 }
@@ -138,6 +264,12 @@ impl Instance for RNG1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_ahb5rstsetr.write(|w| w.rng1rst().set_bit());
+        rcc.rcc_ahb5rstclrr.write(|w| w.rng1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         let csi = rcc::csi::Csi::new();
         csi.frequency() as f32
@@ -175,6 +307,12 @@ impl Instance for RNG2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_ahb3rstsetr.write(|w| w.rng2rst().set_bit());
+        rcc.rcc_ahb3rstclrr.write(|w| w.rng2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         let csi = rcc::csi::Csi::new();
         csi.frequency() as f32
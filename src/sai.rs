@@ -32,6 +32,9 @@ pub type Sai3 = Sai<SAI3>;
 /// Type alias for SAI4.
 pub type Sai4 = Sai<SAI4>;
 
+/// Maximum value of the 6-bit master clock divider field.
+const MAX_MCLK_DIVIDER: u8 = 63;
+
 // ------------------------- Configuration ---------------------------
 
 /// Configuration settings.
@@ -47,9 +50,12 @@ pub struct SaiConfig {
     pub lsb_first: bool,
     /// Master clock generation enable.
     pub mclk_enable: bool,
-    /// Master clock divider.
+    /// Master clock divider, range is 0-63.
+    ///
+    /// MCLK is the kernel clock divided by this value, 0 and 1 both bypass the divider.
+    /// See `SaiConfig::mclk_divider_for()`.
     pub mclk_divider: u8,
-    /// Disable clock divider.
+    /// Disable clock divider. MCKDIV is ignored when set.
     pub no_divider: bool,
     /// Oversampling ratio.
     pub oversampling_ratio: OversamplingRatio,
@@ -59,8 +65,8 @@ pub struct SaiConfig {
     pub data_size: DataSize,
     /// Enable DMA transfers.
     pub dma_enable: bool,
-    /// Frame length.
-    pub frame_length: u8,
+    /// Frame length in bits (8 - 256).
+    pub frame_length: u16,
     /// Frame sync length.
     pub frame_sync_length: u8,
     /// Frame sync offset.
@@ -110,6 +116,133 @@ impl Default for SaiConfig {
     }
 }
 
+impl SaiConfig {
+    /// Returns a configuration for the SPDIF protocol:
+    /// - Master transmitter.
+    /// - 24-bit data size.
+    ///
+    /// Frame and slot settings are not used by the hardware in this mode.
+    pub fn spdif() -> Self {
+        Self {
+            mode: SaiMode::MasterTransmitter,
+            mono: false,
+            protocol: Protocol::Spdif,
+            data_size: DataSize::Bits24,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a configuration for the AC'97 protocol:
+    /// - Master transmitter.
+    /// - 20-bit data size.
+    ///
+    /// Frame and slot settings are fixed to 13 slots in a 256-bit frame by the hardware.
+    pub fn ac97() -> Self {
+        Self {
+            mode: SaiMode::MasterTransmitter,
+            mono: false,
+            protocol: Protocol::Ac97,
+            data_size: DataSize::Bits20,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a TDM configuration:
+    /// - Master transmitter.
+    /// - `slots` slots of 16 bits, or 32 bits for data sizes above 16 bits, all enabled.
+    /// - Frame sync as one bit wide pulse before the first bit of slot 0.
+    pub fn tdm(slots: u8, data_size: DataSize) -> Self {
+        let (slot_size, slot_bits) = if data_size.bits() <= 16 {
+            (SlotSize::Bits16, 16)
+        } else {
+            (SlotSize::Bits32, 32)
+        };
+
+        Self {
+            mode: SaiMode::MasterTransmitter,
+            mono: false,
+            protocol: Protocol::Free,
+            data_size,
+            frame_length: slots as u16 * slot_bits,
+            frame_sync_length: 1,
+            frame_sync_offset: FrameSyncOffset::BeforeFirstBit,
+            frame_sync_polarity: FrameSyncPolarity::ActiveHigh,
+            frame_sync_definition: FrameSyncDefinition::StartFrame,
+            slot_size,
+            slot_enable: ((1u32 << slots.min(16)) - 1) as u16,
+            slot_num: slots,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the master clock divider value that gets closest to a target frequency.
+    /// - `kernel_freq`: Kernel clock frequency in Hz.
+    /// - `target_mclk`: Desired MCLK frequency in Hz.
+    ///
+    /// The result is limited to the range of MCKDIV, 0 is returned for bypassing the divider.
+    pub fn mclk_divider_for(kernel_freq: u32, target_mclk: u32) -> u8 {
+        let divider = (kernel_freq + target_mclk.max(1) / 2) / target_mclk.max(1);
+
+        if divider <= 1 {
+            0
+        } else {
+            divider.min(MAX_MCLK_DIVIDER as u32) as u8
+        }
+    }
+
+    /// Checks the configuration for settings that are incompatible with the protocol.
+    ///
+    /// A master clock divider is rejected together with `no_divider`, because it would
+    /// silently be ignored.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.mclk_divider > MAX_MCLK_DIVIDER
+            || (self.mclk_enable && self.no_divider && self.mclk_divider != 0)
+        {
+            return Err(Error::InvalidConfig);
+        }
+
+        match self.protocol {
+            Protocol::Free => {
+                let slot_bits = match self.slot_size {
+                    SlotSize::DataSize => self.data_size.bits() as u16,
+                    SlotSize::Bits16 => 16,
+                    SlotSize::Bits32 => 32,
+                };
+
+                // All slots must fit into the frame.
+                if !(8..=256).contains(&self.frame_length)
+                    || !(1..=16).contains(&self.slot_num)
+                    || self.slot_num as u16 * slot_bits > self.frame_length
+                {
+                    Err(Error::InvalidConfig)
+                } else {
+                    Ok(())
+                }
+            }
+            Protocol::Spdif => {
+                // SPDIF is output only and carries up to 24-bit samples.
+                if !matches!(
+                    self.mode,
+                    SaiMode::MasterTransmitter | SaiMode::SlaveTransmitter
+                ) || self.data_size == DataSize::Bits32
+                {
+                    Err(Error::InvalidConfig)
+                } else {
+                    Ok(())
+                }
+            }
+            Protocol::Ac97 => {
+                // AC'97 slots carry 16 or 20 bits of data.
+                if !matches!(self.data_size, DataSize::Bits16 | DataSize::Bits20) {
+                    Err(Error::InvalidConfig)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
 /// SAI mode.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum SaiMode {
@@ -221,6 +354,20 @@ impl From<DataSize> for u8 {
     }
 }
 
+impl DataSize {
+    /// Returns the number of bits.
+    pub fn bits(&self) -> u8 {
+        match self {
+            DataSize::Bits8 => 8,
+            DataSize::Bits10 => 10,
+            DataSize::Bits16 => 16,
+            DataSize::Bits20 => 20,
+            DataSize::Bits24 => 24,
+            DataSize::Bits32 => 32,
+        }
+    }
+}
+
 /// Frame synchonization offset.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum FrameSyncOffset {
@@ -296,6 +443,21 @@ impl From<SlotSize> for u8 {
     }
 }
 
+// ----------------------------- Errors -------------------------------
+
+/// Errors
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Error {
+    /// Configuration settings are incompatible.
+    InvalidConfig,
+    /// Number of samples does not match the number of enabled slots.
+    SlotCount,
+}
+
+/// FIFO level value for a full FIFO.
+const FIFO_LEVEL_FULL: u8 = 0b101;
+
 // ------------------------- Implementation ---------------------------
 
 impl<R> Sai<R>
@@ -308,7 +470,9 @@ where
     }
 
     /// Initializes block A.
-    pub fn init_block_a(&mut self, config: SaiConfig) {
+    pub fn init_block_a(&mut self, config: SaiConfig) -> Result<(), Error> {
+        config.validate()?;
+
         R::enable_clock();
 
         self.disable_block_a();
@@ -348,7 +512,7 @@ where
                     .fspol()
                     .bit(config.frame_sync_polarity.into())
                     .frl()
-                    .bits(config.frame_length - 1)
+                    .bits((config.frame_length - 1) as u8)
                     .fsall()
                     .bits(config.frame_sync_length - 1)
             });
@@ -376,10 +540,14 @@ where
         }
 
         self.enable_block_a();
+
+        Ok(())
     }
 
     /// Initializes block B.
-    pub fn init_block_b(&mut self, config: SaiConfig) {
+    pub fn init_block_b(&mut self, config: SaiConfig) -> Result<(), Error> {
+        config.validate()?;
+
         R::enable_clock();
 
         self.disable_block_b();
@@ -419,7 +587,7 @@ where
                     .fspol()
                     .bit(config.frame_sync_polarity.into())
                     .frl()
-                    .bits(config.frame_length - 1)
+                    .bits((config.frame_length - 1) as u8)
                     .fsall()
                     .bits(config.frame_sync_length - 1)
             });
@@ -447,12 +615,53 @@ where
         }
 
         self.enable_block_b();
+
+        Ok(())
+    }
+
+    /// Writes one TDM frame to block A, blocking until all samples are in the FIFO.
+    /// - `slots`: One sample for each enabled slot in ascending slot order.
+    pub fn write_tdm_frame_a(&mut self, slots: &[u32]) -> Result<(), Error> {
+        let regs = R::registers();
+        let slotr = regs.sai_aslotr.read();
+        if slots.len() != enabled_slots(slotr.sloten().bits(), slotr.nbslot().bits()) {
+            return Err(Error::SlotCount);
+        }
+
+        for sample in slots {
+            while regs.sai_asr.read().flvl().bits() == FIFO_LEVEL_FULL {}
+            unsafe {
+                regs.sai_adr.write(|w| w.bits(*sample));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes one TDM frame to block B, blocking until all samples are in the FIFO.
+    /// - `slots`: One sample for each enabled slot in ascending slot order.
+    pub fn write_tdm_frame_b(&mut self, slots: &[u32]) -> Result<(), Error> {
+        let regs = R::registers();
+        let slotr = regs.sai_bslotr.read();
+        if slots.len() != enabled_slots(slotr.sloten().bits(), slotr.nbslot().bits()) {
+            return Err(Error::SlotCount);
+        }
+
+        for sample in slots {
+            while regs.sai_bsr.read().flvl().bits() == FIFO_LEVEL_FULL {}
+            unsafe {
+                regs.sai_bdr.write(|w| w.bits(*sample));
+            }
+        }
+
+        Ok(())
     }
 
     /// Deinitializes the peripheral completely (block A & B).
     pub fn deinit(&mut self) {
         self.disable_block_a();
         self.disable_block_b();
+        R::reset();
         R::disable_clock();
     }
 
@@ -488,6 +697,12 @@ where
     }
 }
 
+/// Returns the number of enabled slots within the configured slot count.
+fn enabled_slots(slot_enable: u16, nbslot: u8) -> usize {
+    let mask = ((1u32 << (nbslot + 1)) - 1) as u16;
+    (slot_enable & mask).count_ones() as usize
+}
+
 // ---------------------------- Instance ------------------------------
 
 /// Trait for instance specific functions.
@@ -501,6 +716,9 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
 }
@@ -536,6 +754,12 @@ impl Instance for SAI1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.sai1rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.sai1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::pll4_q_frequency()
     }
@@ -572,6 +796,12 @@ impl Instance for SAI2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.sai2rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.sai2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::pll4_q_frequency()
     }
@@ -608,6 +838,12 @@ impl Instance for SAI3 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.sai3rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.sai3rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::pll4_q_frequency()
     }
@@ -644,6 +880,12 @@ impl Instance for SAI4 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb3rstsetr.write(|w| w.sai4rst().set_bit());
+        rcc.rcc_apb3rstclrr.write(|w| w.sai4rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::pll4_q_frequency()
     }
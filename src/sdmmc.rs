@@ -27,6 +27,12 @@ where
     /// Bus width.
     bus_width: BusWidth,
 
+    /// Data rate signaling requested by the configuration.
+    data_rate: DataRate,
+
+    /// High bus speed mode requested by the configuration.
+    high_bus_speed: bool,
+
     /// Phantom register block.
     _regs: PhantomData<R>,
 }
@@ -46,6 +52,19 @@ const CARD_INIT_TIMEOUT: u64 = 1000;
 /// Card clock frequency in Hz set after initialization.
 const CARD_CLOCK_FREQUENCY: u32 = 25000000;
 
+/// Card clock frequency in Hz set after switching to high speed mode.
+const HIGH_SPEED_CLOCK_FREQUENCY: u32 = 50000000;
+
+/// Maximum value of the 10-bit clock divider field.
+const MAX_CLOCK_DIVIDER: u32 = 0x3FF;
+
+/// Minimum clock divider while hardware flow control is enabled.
+///
+/// Hardware flow control must not be used with the divider bypassed (CLKDIV = 0),
+/// because the stopped clock can corrupt data on the bus. See the SDMMC section of the
+/// STM32MP151/153/157 device errata sheet ES0438.
+const HWFC_MIN_CLOCK_DIVIDER: u16 = 1;
+
 // ------------------------- Configuration ---------------------------
 
 /// Configuration settings.
@@ -58,10 +77,20 @@ pub struct SdmmcConfig {
     /// Clock power save (disable when bus is inactive)
     pub clock_power_save: bool,
     /// Hardware flow control.
+    ///
+    /// Stops the bus clock when the FIFO is full on receive or empty on transmit,
+    /// which prevents overrun and underrun errors if the FIFO is not serviced in time.
+    /// Due to a device erratum (see ES0438, SDMMC section) it can't be combined with a
+    /// bypassed clock divider, so the bus clock must not exceed half of the kernel clock
+    /// when enabled.
     pub hardware_flow_control: bool,
     /// Data rate signaling.
+    ///
+    /// Only applied by `apply_bus_speed_mode()` after the card was switched via CMD6.
     pub data_rate: DataRate,
     /// Enable SDR50, DDR50, SDR104, HS200 bus speed modes.
+    ///
+    /// Only applied by `apply_bus_speed_mode()` after the card was switched via CMD6.
     pub high_bus_speed: bool,
     /// Data timeout in bus cycles.
     pub data_timeout: u32,
@@ -84,6 +113,24 @@ impl Default for SdmmcConfig {
     }
 }
 
+impl SdmmcConfig {
+    /// Checks the configuration for illegal bus speed mode combinations.
+    ///
+    /// The bus speed modes map to the settings as follows:
+    /// - DS, HS, SDR12, SDR25: `high_bus_speed` off, SDR.
+    /// - SDR50, SDR104, HS200: `high_bus_speed` on, SDR.
+    /// - DDR50: `high_bus_speed` on, DDR, 4 or 8 bit bus width.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.data_rate == DataRate::Ddr
+            && (!self.high_bus_speed || self.bus_width == BusWidth::Bits1)
+        {
+            return Err(Error::InvalidConfig);
+        }
+
+        Ok(())
+    }
+}
+
 /// Bus width.
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub enum BusWidth {
@@ -146,6 +193,90 @@ impl Default for CommandConfig {
     }
 }
 
+/// Decoded status of CMD6 - SWITCH_FUNC.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SwitchStatus {
+    /// Maximum current consumption in mA with the selected functions, 0 on error.
+    pub max_current: u16,
+    /// Supported functions bitmask of the groups 1 - 6 (index 0 - 5).
+    pub group_support: [u16; 6],
+    /// Selected function of the groups 1 - 6 (index 0 - 5), 0xF if not switchable.
+    pub group_selection: [u8; 6],
+}
+
+impl SwitchStatus {
+    /// Decodes the 64-byte status block, which is sent MSB first.
+    fn from_bytes(bytes: &[u8; 64]) -> Self {
+        let word = |index: usize| u16::from_be_bytes([bytes[index], bytes[index + 1]]);
+
+        Self {
+            max_current: word(0),
+            group_support: [word(12), word(10), word(8), word(6), word(4), word(2)],
+            group_selection: [
+                bytes[16] & 0x0F,
+                bytes[16] >> 4,
+                bytes[15] & 0x0F,
+                bytes[15] >> 4,
+                bytes[14] & 0x0F,
+                bytes[14] >> 4,
+            ],
+        }
+    }
+
+    /// Returns if a function 0 - 15 of a group 1 - 6 is supported.
+    ///
+    /// Returns `false` for a group or function out of range.
+    pub fn is_supported(&self, group: u8, function: u8) -> bool {
+        match (group as usize)
+            .checked_sub(1)
+            .and_then(|index| self.group_support.get(index))
+        {
+            Some(support) => function < 16 && support & (1 << function) != 0,
+            None => false,
+        }
+    }
+
+    /// Returns the selected function of a group 1 - 6.
+    ///
+    /// Returns `None` for a group out of range.
+    pub fn selected_function(&self, group: u8) -> Option<u8> {
+        (group as usize)
+            .checked_sub(1)
+            .and_then(|index| self.group_selection.get(index))
+            .copied()
+    }
+
+    /// Returns if high speed / SDR25 is supported (group 1, function 1).
+    pub fn supports_high_speed(&self) -> bool {
+        self.is_supported(1, 1)
+    }
+
+    /// Returns if UHS-I SDR50 is supported (group 1, function 2).
+    pub fn supports_sdr50(&self) -> bool {
+        self.is_supported(1, 2)
+    }
+
+    /// Returns if UHS-I SDR104 is supported (group 1, function 3).
+    pub fn supports_sdr104(&self) -> bool {
+        self.is_supported(1, 3)
+    }
+
+    /// Returns if UHS-I DDR50 is supported (group 1, function 4).
+    pub fn supports_ddr50(&self) -> bool {
+        self.is_supported(1, 4)
+    }
+
+    /// Returns the highest supported current limit in mA (group 4).
+    pub fn max_current_limit(&self) -> u16 {
+        match self.group_support[3] {
+            x if x & (1 << 3) != 0 => 800,
+            x if x & (1 << 2) != 0 => 600,
+            x if x & (1 << 1) != 0 => 400,
+            _ => 200,
+        }
+    }
+}
+
 // ----------------------------- Errors -------------------------------
 
 /// Errors
@@ -168,6 +299,12 @@ pub enum Error {
     ReceiveOverrun,
     /// Transmit underrun.
     TransmitUnderrun,
+    /// Configuration settings are incompatible.
+    InvalidConfig,
+    /// Hardware flow control is not allowed with the requested clock frequency.
+    HardwareFlowControl,
+    /// Clock frequency is out of the range of the clock divider.
+    ClockFrequency,
 }
 
 // ------------------------- Implementation ---------------------------
@@ -182,6 +319,8 @@ where
             cid: None,
             rca: None,
             bus_width: BusWidth::Bits1,
+            data_rate: DataRate::Sdr,
+            high_bus_speed: false,
             _regs: PhantomData,
         }
     }
@@ -192,7 +331,22 @@ where
     }
 
     /// Initializes the peripheral.
-    pub fn init(&mut self, config: SdmmcConfig) {
+    ///
+    /// The bus is always started in default speed SDR mode, because the card has not been
+    /// switched to any other mode yet. The requested mode is applied later by
+    /// `apply_bus_speed_mode()`.
+    ///
+    /// Returns `Error::ClockFrequency` if the init clock frequency can't be set with the
+    /// clock divider and `Error::HardwareFlowControl` if hardware flow control is enabled
+    /// and the init clock frequency would require the clock divider to be bypassed.
+    pub fn init(&mut self, config: SdmmcConfig) -> Result<(), Error> {
+        config.validate()?;
+
+        let clk_div = Self::clock_divider(config.init_clock_frequency)?;
+        if config.hardware_flow_control && clk_div < HWFC_MIN_CLOCK_DIVIDER {
+            return Err(Error::HardwareFlowControl);
+        }
+
         R::enable_clock();
 
         let regs = R::registers();
@@ -208,9 +362,9 @@ where
                     .hwfc_en()
                     .bit(config.hardware_flow_control)
                     .ddr()
-                    .bit(config.data_rate == DataRate::Ddr)
+                    .clear_bit()
                     .busspeed()
-                    .bit(config.high_bus_speed)
+                    .clear_bit()
                     .selclkrx()
                     .bits(0b00)
             });
@@ -218,11 +372,36 @@ where
             regs.sdmmc_argr.write(|w| w.bits(0));
         }
 
-        self.set_clock_frequency(config.init_clock_frequency);
+        self.set_clock_frequency(config.init_clock_frequency)?;
         self.set_data_timeout(config.data_timeout);
         self.bus_width = config.bus_width;
+        self.data_rate = config.data_rate;
+        self.high_bus_speed = config.high_bus_speed;
 
         self.enable();
+
+        Ok(())
+    }
+
+    /// Deinitializes the peripheral.
+    pub fn deinit(&mut self) {
+        self.disable();
+        R::reset();
+        R::disable_clock();
+    }
+
+    /// Applies the bus speed mode and data rate from the configuration.
+    ///
+    /// This must only be called after the card was switched to the matching
+    /// bus speed mode via CMD6, otherwise the signaling does not match the card.
+    pub fn apply_bus_speed_mode(&mut self) {
+        let regs = R::registers();
+        regs.sdmmc_clkcr.modify(|_, w| {
+            w.ddr()
+                .bit(self.data_rate == DataRate::Ddr)
+                .busspeed()
+                .bit(self.high_bus_speed)
+        });
     }
 
     /// Initializes the card.
@@ -359,32 +538,91 @@ where
             self.wait_for_command_response()?;
         }
 
-        self.set_clock_frequency(CARD_CLOCK_FREQUENCY);
+        self.set_clock_frequency(CARD_CLOCK_FREQUENCY)?;
 
         Ok(())
     }
 
     /// Reads a block of 512 bytes from the card.
     pub fn read_block(&mut self, address: u32, buffer: &mut [u8; 512]) -> Result<(), Error> {
+        // Read block via CMD17 - READ_SINGLE_BLOCK.
+        self.read_data(
+            CommandConfig {
+                index: 17,
+                argument: address,
+                response: CommandResponse::Short,
+                data_transfer: true,
+                ..Default::default()
+            },
+            buffer,
+        )
+    }
+
+    /// Queries the functions supported by the card without switching via CMD6 in check mode.
+    pub fn query_switch_functions(&mut self) -> Result<SwitchStatus, Error> {
+        // All groups set to 0xF keep the current function.
+        self.switch_function(0x00FFFFFF)
+    }
+
+    /// Switches the card to high speed mode (50MHz) and raises the clock frequency.
+    ///
+    /// Returns `Error::UnsupportedCard` if the card doesn't offer high speed mode.
+    pub fn enable_high_speed(&mut self) -> Result<(), Error> {
+        if !self.query_switch_functions()?.supports_high_speed() {
+            return Err(Error::UnsupportedCard);
+        }
+
+        // Switch function group 1 (access mode) to function 1 (high speed).
+        let status = self.switch_function(0x80FFFFF1)?;
+        if status.selected_function(1) != Some(1) {
+            return Err(Error::UnsupportedCard);
+        }
+
+        self.set_clock_frequency(HIGH_SPEED_CLOCK_FREQUENCY)?;
+
+        Ok(())
+    }
+
+    /// Sends CMD6 - SWITCH_FUNC with an argument and returns the decoded status.
+    fn switch_function(&mut self, argument: u32) -> Result<SwitchStatus, Error> {
+        let mut buffer = [0; 64];
+        self.read_data(
+            CommandConfig {
+                index: 6,
+                argument,
+                response: CommandResponse::Short,
+                data_transfer: true,
+                ..Default::default()
+            },
+            &mut buffer,
+        )?;
+
+        Ok(SwitchStatus::from_bytes(&buffer))
+    }
+
+    /// Sends a command and reads a single block of data into the buffer.
+    ///
+    /// The buffer length is used as block size and must be a power of 2 from 4 to 512 bytes.
+    fn read_data(&mut self, command: CommandConfig, buffer: &mut [u8]) -> Result<(), Error> {
         while self.is_busy() {}
 
         self.clear_all_data_flags();
 
         let regs = R::registers();
+        let length = buffer.len();
 
         unsafe {
-            regs.sdmmc_dlenr.write(|w| w.datalength().bits(512));
-            regs.sdmmc_dctrl
-                .write(|w| w.dblocksize().bits(9).dtdir().set_bit());
+            regs.sdmmc_dlenr
+                .write(|w| w.datalength().bits(length as u32));
+            regs.sdmmc_dctrl.write(|w| {
+                w.dblocksize()
+                    .bits(length.trailing_zeros() as u8)
+                    .dtdir()
+                    .set_bit()
+            });
         }
 
-        self.send_command(CommandConfig {
-            index: 17,
-            argument: address,
-            response: CommandResponse::Short,
-            data_transfer: true,
-            ..Default::default()
-        });
+        self.send_command(command);
         self.wait_for_command_response()?;
 
         let mut i = 0;
@@ -398,7 +636,7 @@ where
                 return Err(Error::ReceiveOverrun);
             }
 
-            if self.is_receiver_half_full() {
+            if self.is_receiver_half_full() && i + 32 <= length {
                 for _ in 0..8 {
                     let bytes = regs.sdmmc_fifor0.read().bits().to_le_bytes();
                     buffer[i..i + 4].copy_from_slice(&bytes);
@@ -407,16 +645,49 @@ where
             }
         }
 
+        // Read remaining data that didn't fill the FIFO half.
+        while !self.is_receiver_empty() && i < length {
+            let bytes = regs.sdmmc_fifor0.read().bits().to_le_bytes();
+            buffer[i..i + 4].copy_from_slice(&bytes);
+            i += 4;
+        }
+
         Ok(())
     }
 
     /// Sets the clock frequency in Hz.
-    pub fn set_clock_frequency(&mut self, frequency: u32) {
-        let clk_div = (R::clock_frequency() as u32 / frequency / 2) as u16;
+    ///
+    /// The divider is rounded up, so the resulting frequency never exceeds the requested one.
+    /// Use `actual_clock_frequency()` to get the frequency that is really set.
+    ///
+    /// Returns `Error::ClockFrequency` if the frequency is too low for the divider field
+    /// and `Error::HardwareFlowControl` if hardware flow control is enabled and the
+    /// frequency would require the divider to be bypassed. The clock is not changed then.
+    pub fn set_clock_frequency(&mut self, frequency: u32) -> Result<(), Error> {
+        let regs = R::registers();
+        let clk_div = Self::clock_divider(frequency)?;
+
+        if regs.sdmmc_clkcr.read().hwfc_en().bit_is_set() && clk_div < HWFC_MIN_CLOCK_DIVIDER {
+            return Err(Error::HardwareFlowControl);
+        }
+
         unsafe {
-            let regs = R::registers();
             regs.sdmmc_clkcr.modify(|_, w| w.clkdiv().bits(clk_div));
         }
+
+        Ok(())
+    }
+
+    /// Returns the clock divider for a frequency in Hz.
+    fn clock_divider(frequency: u32) -> Result<u16, Error> {
+        clock_divider(R::clock_frequency() as u32, frequency).ok_or(Error::ClockFrequency)
+    }
+
+    /// Returns the actual clock frequency in Hz as set by the clock divider.
+    pub fn actual_clock_frequency(&self) -> u32 {
+        let regs = R::registers();
+        let clk_div = regs.sdmmc_clkcr.read().clkdiv().bits();
+        clock_frequency(R::clock_frequency() as u32, clk_div)
     }
 
     /// Sets the data timeout in bus clock cycles.
@@ -492,6 +763,14 @@ where
         }
     }
 
+    /// Disables the peripheral and powers off the card clock.
+    pub fn disable(&mut self) {
+        unsafe {
+            let regs = R::registers();
+            regs.sdmmc_power.modify(|_, w| w.pwrctrl().bits(0b00));
+        }
+    }
+
     /// Returns if the peripheral is enabled.
     pub fn is_enabled(&self) -> bool {
         let regs = R::registers();
@@ -698,6 +977,40 @@ where
     }
 }
 
+/// Returns the clock divider for a frequency, rounded up.
+/// - `kernel_frequency`: Kernel clock frequency in Hz.
+/// - `frequency`: Requested bus clock frequency in Hz.
+///
+/// Returns `None` if the divider exceeds the range of the divider field.
+fn clock_divider(kernel_frequency: u32, frequency: u32) -> Option<u16> {
+    // SDMMC_CK = kernel / (2 * CLKDIV), CLKDIV = 0 bypasses the divider.
+    if frequency >= kernel_frequency {
+        return Some(0);
+    }
+
+    if frequency == 0 {
+        return None;
+    }
+
+    let clk_div = kernel_frequency.div_ceil(2 * frequency);
+    if clk_div > MAX_CLOCK_DIVIDER {
+        None
+    } else {
+        Some(clk_div as u16)
+    }
+}
+
+/// Returns the bus clock frequency in Hz resulting from a clock divider.
+/// - `kernel_frequency`: Kernel clock frequency in Hz.
+/// - `clk_div`: Value of the divider field.
+fn clock_frequency(kernel_frequency: u32, clk_div: u16) -> u32 {
+    if clk_div == 0 {
+        kernel_frequency
+    } else {
+        kernel_frequency / (2 * clk_div as u32)
+    }
+}
+
 // ---------------------------- Instance ------------------------------
 
 /// Trait for instance specific functions.
@@ -711,6 +1024,9 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
 }
@@ -746,6 +1062,12 @@ impl Instance for SDMMC1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_ahb6rstsetr.write(|w| w.sdmmc1rst().set_bit());
+        rcc.rcc_ahb6rstclrr.write(|w| w.sdmmc1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::hsi_frequency() as f32
     }
@@ -782,6 +1104,12 @@ impl Instance for SDMMC2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_ahb6rstsetr.write(|w| w.sdmmc2rst().set_bit());
+        rcc.rcc_ahb6rstclrr.write(|w| w.sdmmc2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::hsi_frequency() as f32
     }
@@ -818,7 +1146,77 @@ impl Instance for SDMMC3 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_ahb2rstsetr.write(|w| w.sdmmc3rst().set_bit());
+        rcc.rcc_ahb2rstclrr.write(|w| w.sdmmc3rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
         rcc::mcu_frequency()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Init clock frequency used for SD cards.
+    const INIT_FREQUENCY: u32 = 400000;
+
+    #[test]
+    fn init_clock_does_not_exceed_400khz() {
+        for kernel in [24000000, 64000000, 99000000, 196000000, 208877930] {
+            let clk_div = clock_divider(kernel, INIT_FREQUENCY).unwrap();
+            assert!(clock_frequency(kernel, clk_div) <= INIT_FREQUENCY);
+
+            // The next smaller divider would exceed the frequency.
+            assert!(clock_frequency(kernel, clk_div - 1) > INIT_FREQUENCY);
+        }
+    }
+
+    #[test]
+    fn init_clock_exact_divider() {
+        assert_eq!(clock_divider(24000000, INIT_FREQUENCY), Some(30));
+        assert_eq!(clock_divider(196000000, INIT_FREQUENCY), Some(245));
+        assert_eq!(clock_frequency(196000000, 245), INIT_FREQUENCY);
+    }
+
+    #[test]
+    fn init_clock_rounds_up() {
+        assert_eq!(clock_divider(99000000, INIT_FREQUENCY), Some(124));
+        assert_eq!(clock_frequency(99000000, 124), 399193);
+    }
+
+    #[test]
+    fn divider_bypassed_at_kernel_frequency() {
+        assert_eq!(clock_divider(99000000, 99000000), Some(0));
+        assert_eq!(clock_frequency(99000000, 0), 99000000);
+    }
+
+    #[test]
+    fn switch_status_out_of_range() {
+        let mut bytes = [0; 64];
+        // Group 1 supports all functions, function 1 selected.
+        bytes[12] = 0xFF;
+        bytes[13] = 0xFF;
+        bytes[16] = 0x01;
+        let status = SwitchStatus::from_bytes(&bytes);
+
+        assert!(status.is_supported(1, 15));
+        assert!(!status.is_supported(1, 16));
+        assert!(!status.is_supported(0, 1));
+        assert!(!status.is_supported(7, 1));
+        assert_eq!(status.selected_function(1), Some(1));
+        assert_eq!(status.selected_function(0), None);
+        assert_eq!(status.selected_function(7), None);
+    }
+
+    #[test]
+    fn divider_out_of_range() {
+        // 99 MHz / (2 * 1023) is about 48.4 kHz.
+        assert_eq!(clock_divider(99000000, 48387), None);
+        assert_eq!(clock_divider(99000000, 48388), Some(1023));
+        assert_eq!(clock_divider(99000000, 0), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Software SPI using GPIO pins.
+//!
+//! Only master mode with 8-bit words is supported.
+
+use embedded_hal as eh;
+
+use crate::gpio::{Pin, PinMode, PinState};
+use crate::spi::{ClockPhase, ClockPolarity};
+use crate::time;
+
+/// Software SPI.
+#[derive(Debug)]
+pub struct SoftSpi {
+    /// Clock output pin.
+    sck: Pin,
+    /// Data output pin.
+    mosi: Pin,
+    /// Data input pin.
+    miso: Pin,
+    /// Configuration settings.
+    config: SoftSpiConfig,
+}
+
+// ------------------------- Configuration ---------------------------
+
+/// Configuration settings.
+#[derive(Debug, Clone)]
+pub struct SoftSpiConfig {
+    /// Clock idle polarity.
+    pub clock_polarity: ClockPolarity,
+    /// Clock capture transition phase.
+    pub clock_phase: ClockPhase,
+    /// Least significant bit first.
+    pub lsb_first: bool,
+    /// Delay in microseconds for each half of a clock period.
+    pub half_period_us: u32,
+}
+
+impl Default for SoftSpiConfig {
+    /// Returns the default configuration:
+    /// - Clock idle polarity low (CPOL=0).
+    /// - Clock data capture on first transition (CPHA=0).
+    /// - MSB first.
+    /// - 5us half period, resulting in a clock of less than 100kHz.
+    fn default() -> Self {
+        Self {
+            clock_polarity: ClockPolarity::Low,
+            clock_phase: ClockPhase::First,
+            lsb_first: false,
+            half_period_us: 5,
+        }
+    }
+}
+
+// ------------------------- Implementation ---------------------------
+
+impl SoftSpi {
+    /// Returns an instance using the given pins.
+    pub fn new(sck: Pin, mosi: Pin, miso: Pin, config: SoftSpiConfig) -> Self {
+        Self {
+            sck,
+            mosi,
+            miso,
+            config,
+        }
+    }
+
+    /// Initializes the pins and sets the clock to idle level.
+    pub fn init(&mut self) {
+        self.sck.set_output_state(self.idle_state());
+        self.sck.apply_mode(PinMode::Output);
+        self.mosi.apply_mode(PinMode::Output);
+        self.miso.apply_mode(PinMode::Input);
+    }
+
+    /// Transfers a single byte and returns the received byte.
+    pub fn transfer_byte(&mut self, byte: u8) -> u8 {
+        let idle = self.idle_state();
+        let active = !idle;
+        let mut received = 0;
+
+        for i in 0..8 {
+            let bit = if self.config.lsb_first { i } else { 7 - i };
+            let output = PinState::from(byte & (1 << bit) != 0);
+
+            let input = match self.config.clock_phase {
+                ClockPhase::First => {
+                    self.mosi.set_output_state(output);
+                    time::delay_us(self.config.half_period_us);
+                    self.sck.set_output_state(active);
+                    let input = self.miso.get_input_state();
+                    time::delay_us(self.config.half_period_us);
+                    self.sck.set_output_state(idle);
+                    input
+                }
+                ClockPhase::Second => {
+                    self.sck.set_output_state(active);
+                    self.mosi.set_output_state(output);
+                    time::delay_us(self.config.half_period_us);
+                    self.sck.set_output_state(idle);
+                    let input = self.miso.get_input_state();
+                    time::delay_us(self.config.half_period_us);
+                    input
+                }
+            };
+
+            if input == PinState::High {
+                received |= 1 << bit;
+            }
+        }
+
+        received
+    }
+
+    /// Returns the pins (SCK, MOSI, MISO).
+    pub fn release(self) -> (Pin, Pin, Pin) {
+        (self.sck, self.mosi, self.miso)
+    }
+
+    /// Returns the clock level when idle.
+    fn idle_state(&self) -> PinState {
+        PinState::from(self.config.clock_polarity == ClockPolarity::High)
+    }
+}
+
+// --------------------------- embedded-hal ---------------------------
+
+impl eh::spi::ErrorType for SoftSpi {
+    type Error = core::convert::Infallible;
+}
+
+impl eh::spi::SpiBus for SoftSpi {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        for word in words {
+            *word = self.transfer_byte(0x00);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        for word in words {
+            self.transfer_byte(*word);
+        }
+
+        Ok(())
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        for i in 0..read.len().max(write.len()) {
+            let received = self.transfer_byte(write.get(i).copied().unwrap_or(0x00));
+            if let Some(word) = read.get_mut(i) {
+                *word = received;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        for word in words {
+            *word = self.transfer_byte(*word);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
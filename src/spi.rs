@@ -1,12 +1,23 @@
 //! Serial peripheral interface.
+//!
+//! The peripheral has no internal loopback mode. For a self test of the full-duplex
+//! transfer, MOSI has to be connected to MISO externally.
 
+use core::cell::Cell;
 use core::marker::PhantomData;
 use core::ops::Deref;
 
 use cfg_if::cfg_if;
+use embedded_hal as eh;
 
+use crate::dma::{
+    CircularTransfer, DataSize, DmaConfigError, DmaRequestInput, DmaStream, DmaStreamConfig,
+    TransferDirection,
+};
+use crate::gpio::{Pin, PinMode};
 use crate::pac;
 use crate::rcc;
+use crate::time::{self, Instant};
 use pac::spi1::RegisterBlock;
 use pac::{SPI1, SPI2, SPI3, SPI4, SPI5, SPI6};
 
@@ -18,6 +29,19 @@ where
 {
     /// Phantom register block.
     _regs: PhantomData<R>,
+    /// Settings replaced by `read_stream_circular`, restored by `stop_read_stream`.
+    saved_rx_stream: Option<RxStreamSettings>,
+}
+
+/// Peripheral settings changed for a receive stream.
+#[derive(Debug, Clone, Copy)]
+struct RxStreamSettings {
+    /// RX DMA enable.
+    rx_dma_enable: bool,
+    /// Communication mode bits.
+    communication_mode: u8,
+    /// Automatic suspension in receive mode.
+    master_auto_suspend: bool,
 }
 
 /// Type alias for SPI1.
@@ -38,6 +62,14 @@ pub type Spi5 = Spi<SPI5>;
 /// Type alias for SPI6.
 pub type Spi6 = Spi<SPI6>;
 
+/// Maximum number of frames written ahead of the received ones in full-duplex transfers.
+///
+/// This is the size of the smallest FIFO, so the RxFIFO can't overflow.
+const MAX_FRAMES_IN_FLIGHT: usize = 8;
+
+/// Timeout in milliseconds for a flag to be set in blocking operations.
+const FLAG_TIMEOUT: u64 = 100;
+
 // ------------------------- Configuration ---------------------------
 
 /// Configuration settings.
@@ -65,6 +97,12 @@ pub struct SpiConfig {
     pub ss_output_enable: bool,
     /// SS input/output active polarity.
     pub ss_polarity: SsPolarity,
+    /// Pulse SS inactive between data frames in master mode.
+    ///
+    /// Only effective with `ss_output_enable` in Motorola mode, the length of the pulse
+    /// is set by `master_inter_data_idleness`. In TI mode, the frame pulse is always
+    /// generated by the hardware.
+    pub ss_pulse_mode: bool,
     /// Enable DMA transfers for transmitter.
     pub tx_dma_enable: bool,
     /// Enable DMA transfers for receiver.
@@ -100,6 +138,7 @@ impl Default for SpiConfig {
             protocol_mode: ProtocolMode::Motorola,
             ss_output_enable: true,
             ss_polarity: SsPolarity::Low,
+            ss_pulse_mode: false,
             tx_dma_enable: false,
             rx_dma_enable: false,
             swap_miso_mosi: false,
@@ -123,6 +162,16 @@ pub enum CommunicationMode {
     HalfDuplex = 0b11,
 }
 
+/// Data line direction in half-duplex mode.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum HalfDuplexDir {
+    /// Receiver.
+    Receive = 0b0,
+    /// Transmitter.
+    Transmit = 0b1,
+}
+
 /// Clock prescaler.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
@@ -145,6 +194,44 @@ pub enum ClockPrescaler {
     Div256 = 0b111,
 }
 
+impl TryFrom<u8> for ClockPrescaler {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(ClockPrescaler::Div2),
+            0b001 => Ok(ClockPrescaler::Div4),
+            0b010 => Ok(ClockPrescaler::Div8),
+            0b011 => Ok(ClockPrescaler::Div16),
+            0b100 => Ok(ClockPrescaler::Div32),
+            0b101 => Ok(ClockPrescaler::Div64),
+            0b110 => Ok(ClockPrescaler::Div128),
+            0b111 => Ok(ClockPrescaler::Div256),
+            _ => Err("Invalid value."),
+        }
+    }
+}
+
+impl ClockPrescaler {
+    /// Returns the fastest prescaler with a SCK frequency not exceeding a maximum.
+    /// - `kernel`: Kernel clock frequency in Hz.
+    /// - `target_max`: Maximum SCK frequency in Hz.
+    ///
+    /// `Div256` is returned if the target can't be reached.
+    pub fn for_max_frequency(kernel: u32, target_max: u32) -> ClockPrescaler {
+        (0b000..=0b111)
+            .map(|value| ClockPrescaler::try_from(value).unwrap())
+            // Rounded up, the exact SCK frequency must not exceed the maximum.
+            .find(|prescaler| kernel.div_ceil(prescaler.divider()) <= target_max)
+            .unwrap_or(ClockPrescaler::Div256)
+    }
+
+    /// Returns the division factor.
+    pub fn divider(&self) -> u32 {
+        2 << (*self as u32)
+    }
+}
+
 /// Polarity when clock is idle.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[repr(u8)]
@@ -185,6 +272,38 @@ pub enum SsPolarity {
     High = 0b1,
 }
 
+// ----------------------------- Errors -------------------------------
+
+/// Errors
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Error {
+    /// Instance has no DMA request line.
+    NoDmaRequest,
+    /// Transfer size exceeds the maximum of 65535 bytes.
+    TransferSize,
+    /// DMA transfer error.
+    DmaTransfer,
+    /// Data size is out of range.
+    DataSize,
+    /// Receiver overrun.
+    Overrun,
+    /// Transmitter underrun in slave mode.
+    Underrun,
+    /// Timeout waiting for a flag.
+    Timeout,
+    /// Operation not supported in the configured communication mode.
+    CommunicationMode,
+    /// DMA stream configuration error.
+    DmaConfig(DmaConfigError),
+}
+
+impl From<DmaConfigError> for Error {
+    fn from(value: DmaConfigError) -> Self {
+        Error::DmaConfig(value)
+    }
+}
+
 // ------------------------- Implementation ---------------------------
 
 impl<R> Spi<R>
@@ -193,7 +312,10 @@ where
 {
     /// Returns the peripheral instance.
     pub fn new() -> Self {
-        Self { _regs: PhantomData }
+        Self {
+            _regs: PhantomData,
+            saved_rx_stream: None,
+        }
     }
 
     /// Initializes the peripheral.
@@ -240,6 +362,8 @@ where
                     .bit(config.ss_polarity == SsPolarity::High)
                     .ssoe()
                     .bit(config.ss_output_enable)
+                    .ssom()
+                    .bit(config.ss_pulse_mode)
             });
         }
 
@@ -249,20 +373,745 @@ where
     /// Deinitializes the peripheral.
     pub fn deinit(&mut self) {
         self.disable();
+        R::reset();
         R::disable_clock();
     }
 
+    /// Returns the fastest prescaler with a SCK frequency not exceeding a maximum,
+    /// based on the frequency of the selected kernel clock source.
+    /// - `target_max`: Maximum SCK frequency in Hz.
+    ///
+    /// The kernel clock source has to be set before, e.g. with `rcc::set_spi1_source`.
+    pub fn prescaler_for_max_frequency(target_max: u32) -> ClockPrescaler {
+        ClockPrescaler::for_max_frequency(R::clock_frequency() as u32, target_max)
+    }
+
+    /// Returns the SCK frequency in Hz resulting from kernel clock and prescaler.
+    ///
+    /// The kernel clock frequency is determined from the source selected in the RCC.
+    pub fn sck_frequency(&self) -> u32 {
+        let regs = R::registers();
+        let prescaler = ClockPrescaler::try_from(regs.spi_cfg1.read().mbr().bits()).unwrap();
+        R::clock_frequency() as u32 / prescaler.divider()
+    }
+
+    /// Sets the data frame size without a full initialization.
+    /// - `bits`: Data frame size, range is 4-32 bits.
+    ///
+    /// The transfer size set by `set_transfer_size` counts frames, not bytes,
+    /// so it has to be recalculated after changing the data size.
+    pub fn set_data_size(&mut self, bits: u8) -> Result<(), Error> {
+        if !(4..=32).contains(&bits) {
+            return Err(Error::DataSize);
+        }
+
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg1.modify(|_, w| w.dsize().bits(bits - 1));
+        }
+        if enabled {
+            self.enable();
+        }
+
+        Ok(())
+    }
+
+    /// Sets the clock polarity and phase without a full initialization.
+    pub fn set_mode(&mut self, polarity: ClockPolarity, phase: ClockPhase) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        regs.spi_cfg2.modify(|_, w| {
+            w.cpol()
+                .bit(polarity == ClockPolarity::High)
+                .cpha()
+                .bit(phase == ClockPhase::Second)
+        });
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sets the clock prescaler without a full initialization.
+    pub fn set_prescaler(&mut self, prescaler: ClockPrescaler) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg1.modify(|_, w| w.mbr().bits(prescaler as u8));
+        }
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sets the delay inserted inbetween frames in master mode without a full
+    /// initialization.
+    /// - `cycles`: Delay in clock cycles, range is 0-15.
+    pub fn set_inter_data_idleness(&mut self, cycles: u8) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg2.modify(|_, w| w.midi().bits(cycles.min(15)));
+        }
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sets the delay inserted after SS going active in master mode without a full
+    /// initialization.
+    /// - `cycles`: Delay in clock cycles, range is 0-15.
+    pub fn set_ss_idleness(&mut self, cycles: u8) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg2.modify(|_, w| w.mssi().bits(cycles.min(15)));
+        }
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sets the data line direction in half-duplex mode.
+    ///
+    /// Should only be changed between transfers, e.g. after sending a command to a
+    /// 3-wire device before reading the response.
+    pub fn set_half_duplex_direction(&mut self, dir: HalfDuplexDir) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        regs.spi2s_cr1
+            .modify(|_, w| w.hddir().bit(dir == HalfDuplexDir::Transmit));
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sets if SS is pulsed inactive between data frames in master mode.
+    ///
+    /// See `SpiConfig::ss_pulse_mode` for the constraints.
+    pub fn set_ss_pulse_mode(&mut self, enable: bool) {
+        let enabled = self.is_enabled();
+        self.disable();
+        let regs = R::registers();
+        regs.spi_cfg2.modify(|_, w| w.ssom().bit(enable));
+        if enabled {
+            self.enable();
+        }
+    }
+
     /// Write bytes from a buffer, blocking.
-    pub fn write_bytes(&mut self, data: &[u8]) {
+    ///
+    /// Each byte is a frame, so the data size must not exceed 8 bits. Use `write_words`
+    /// for larger frames. The TxFIFO is filled as far as possible before the transfer
+    /// is started and topped up whenever space is available, so the buffer may be
+    /// larger than the FIFO.
+    ///
+    /// Returns an error if the data size exceeds 8 bits, if an underrun or overrun is
+    /// detected, e.g. in slave mode, or if a flag isn't set within the flag timeout.
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if self.data_size() > 8 {
+            return Err(Error::DataSize);
+        }
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
         self.set_transfer_size(data.len() as u16);
         self.clear_transmission_transfer_filled();
-        for byte in data {
+        self.clear_overrun_error();
+        self.clear_underrun_error();
+
+        // TXP is also valid before the transfer is started, so the FIFO can be
+        // preloaded up to its capacity.
+        let mut bytes = data.iter().peekable();
+        while bytes.peek().is_some() && self.is_transmitter_empty() {
+            self.write_tx_fifo_byte(*bytes.next().unwrap());
+        }
+
+        self.start_transfer();
+
+        for byte in bytes {
+            self.wait_for(Self::is_transmitter_empty)?;
             self.write_tx_fifo_byte(*byte);
         }
-        while !self.is_transmission_transfer_filled() {}
+
+        self.wait_end_of_transfer()?;
+        self.clear_transmission_transfer_filled();
+
+        Ok(())
+    }
+
+    /// Writes 16-bit frames from a buffer, blocking.
+    ///
+    /// Intended for data sizes of 9-16 bits, the transfer size counts frames.
+    pub fn write_words(&mut self, data: &[u16]) -> Result<(), Error> {
+        if data.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if !(9..=16).contains(&self.data_size()) {
+            return Err(Error::DataSize);
+        }
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.set_transfer_size(data.len() as u16);
+        self.clear_overrun_error();
+        self.clear_underrun_error();
         self.start_transfer();
-        while !self.is_end_of_transfer() {}
+
+        for word in data {
+            self.wait_for(Self::is_transmitter_empty)?;
+            self.write_tx_fifo_half_word(*word);
+        }
+
+        self.wait_end_of_transfer()?;
+        self.clear_transmission_transfer_filled();
+
+        Ok(())
+    }
+
+    /// Returns an error and disables the peripheral if an overrun or underrun occurred.
+    ///
+    /// The error flag is cleared.
+    fn check_errors(&mut self) -> Result<(), Error> {
+        if self.is_overrun_error() {
+            self.clear_overrun_error();
+            self.disable();
+            return Err(Error::Overrun);
+        }
+
+        if self.is_underrun_error() {
+            self.clear_underrun_error();
+            self.disable();
+            return Err(Error::Underrun);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the data frame size in bits.
+    pub fn data_size(&self) -> u8 {
+        let regs = R::registers();
+        regs.spi_cfg1.read().dsize().bits() + 1
+    }
+
+    /// Returns the communication mode.
+    pub fn communication_mode(&self) -> CommunicationMode {
+        let regs = R::registers();
+        match regs.spi_cfg2.read().comm().bits() {
+            0b00 => CommunicationMode::FullDuplex,
+            0b01 => CommunicationMode::SimplexTransmitter,
+            0b10 => CommunicationMode::SimplexReceiver,
+            _ => CommunicationMode::HalfDuplex,
+        }
+    }
+
+    /// Writes bytes with the method matching the communication mode, blocking.
+    ///
+    /// Received bytes are discarded in full-duplex mode, the direction is set to
+    /// transmit in half-duplex mode.
+    fn write_any_mode(&mut self, data: &[u8]) -> Result<(), Error> {
+        match self.communication_mode() {
+            CommunicationMode::FullDuplex => self.transfer(&mut [], data),
+            CommunicationMode::SimplexTransmitter => self.write_bytes(data),
+            CommunicationMode::HalfDuplex => {
+                self.set_half_duplex_direction(HalfDuplexDir::Transmit);
+                self.write_bytes(data)
+            }
+            CommunicationMode::SimplexReceiver => Err(Error::CommunicationMode),
+        }
+    }
+
+    /// Reads bytes with the method matching the communication mode, blocking.
+    ///
+    /// 0x00 is transmitted in full-duplex mode.
+    fn read_any_mode(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        match self.communication_mode() {
+            CommunicationMode::FullDuplex => self.transfer(buffer, &[]),
+            CommunicationMode::SimplexReceiver | CommunicationMode::HalfDuplex => {
+                self.read_bytes(buffer)
+            }
+            CommunicationMode::SimplexTransmitter => Err(Error::CommunicationMode),
+        }
+    }
+
+    /// Transfers bytes in full-duplex mode, blocking.
+    ///
+    /// `write` is clocked out while the received bytes are stored in `read`. The transfer
+    /// length is the longer of both buffers, missing transmit bytes are sent as 0x00 and
+    /// surplus received bytes are discarded. Requires full-duplex communication mode,
+    /// 8-bit data size and a FIFO threshold level of 1. Returns an error in other
+    /// communication modes.
+    pub fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Error> {
+        self.transfer_frames(
+            read.len().max(write.len()),
+            |i| write.get(i).copied().unwrap_or(0x00),
+            |i, byte| {
+                if let Some(target) = read.get_mut(i) {
+                    *target = byte;
+                }
+            },
+        )
+    }
+
+    /// Transfers bytes in full-duplex mode, replacing the transmitted bytes with the
+    /// received ones, blocking.
+    ///
+    /// Useful for command and response sequences of flash memories and sensors without
+    /// a second buffer. See `transfer` for the requirements.
+    pub fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Error> {
+        // A byte is always transmitted before it is replaced by the received one.
+        let cells = Cell::from_mut(words).as_slice_of_cells();
+        self.transfer_frames(
+            cells.len(),
+            |i| cells[i].get(),
+            |i, byte| cells[i].set(byte),
+        )
+    }
+
+    /// Transfers `len` bytes in full-duplex mode, taking the transmit bytes from `tx`
+    /// and passing the received ones to `rx`, both with their index.
+    fn transfer_frames(
+        &mut self,
+        len: usize,
+        mut tx: impl FnMut(usize) -> u8,
+        mut rx: impl FnMut(usize, u8),
+    ) -> Result<(), Error> {
+        if len > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if self.communication_mode() != CommunicationMode::FullDuplex {
+            return Err(Error::CommunicationMode);
+        }
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        self.set_transfer_size(len as u16);
+        self.clear_overrun_error();
+        self.clear_underrun_error();
+        self.start_transfer();
+
+        let mut written = 0;
+        let mut received = 0;
+        let mut start = Instant::now();
+
+        while received < len {
+            self.check_errors()?;
+
+            if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                self.disable();
+                return Err(Error::Timeout);
+            }
+
+            if written < len
+                && written - received < MAX_FRAMES_IN_FLIGHT
+                && self.is_transmitter_empty()
+            {
+                self.write_tx_fifo_byte(tx(written));
+                written += 1;
+            }
+
+            if self.is_receiver_not_empty() {
+                rx(received, self.read_rx_fifo_byte());
+                received += 1;
+                start = Instant::now();
+            }
+        }
+
+        self.wait_end_of_transfer()?;
+        self.clear_transmission_transfer_filled();
+
+        Ok(())
+    }
+
+    /// Reads bytes into a buffer, blocking.
+    ///
+    /// Intended for simplex receiver and half-duplex mode, the direction is set to
+    /// receive in the latter. The master generates the clock for the length of the
+    /// buffer. Requires 8-bit data size and a FIFO threshold level of 1.
+    pub fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        if buffer.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.disable();
+
+        let regs = R::registers();
+        if regs.spi_cfg2.read().comm().bits() == CommunicationMode::HalfDuplex as u8 {
+            self.set_half_duplex_direction(HalfDuplexDir::Receive);
+        }
+
+        self.set_transfer_size(buffer.len() as u16);
+        self.clear_overrun_error();
+        self.clear_underrun_error();
+        self.start_transfer();
+
+        for byte in buffer.iter_mut() {
+            self.wait_for(Self::is_receiver_not_empty)?;
+            *byte = self.read_rx_fifo_byte();
+        }
+
+        self.wait_end_of_transfer()
+    }
+
+    /// Writes bytes from a buffer using TX DMA, blocking until the transfer is complete.
+    ///
+    /// Only 8-bit data size is supported.
+    /// - `data`: Data to write, at most 65535 bytes.
+    /// - `stream`: DMA stream to use.
+    pub fn write_dma(&mut self, data: &[u8], stream: DmaStream) -> Result<(), Error> {
+        let request_input = R::tx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if data.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::MemoryToPeripheral,
+            memory_data_size: DataSize::Byte,
+            peripheral_data_size: DataSize::Byte,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(data.as_ptr() as u32)?;
+        stream.init(config)?;
+
+        self.disable();
+
+        let regs = R::registers();
+        let tx_dma_enable = regs.spi_cfg1.read().txdmaen().bit_is_set();
+        regs.spi_cfg1.modify(|_, w| w.txdmaen().set_bit());
+
+        stream.start_transfer(
+            data.as_ptr() as u32,
+            regs.spi2s_txdr.as_ptr() as u32,
+            data.len(),
+        );
+
+        self.set_transfer_size(data.len() as u16);
+        self.start_transfer();
+
+        let result = self
+            .wait_dma_stream(stream)
+            .and_then(|_| self.wait_end_of_transfer());
+
+        self.disable();
+        regs.spi_cfg1.modify(|_, w| w.txdmaen().bit(tx_dma_enable));
+
+        result
+    }
+
+    /// Transfers bytes in full-duplex mode using TX and RX DMA, blocking until the
+    /// transfer is complete.
+    ///
+    /// Both buffers must have the same length. Only 8-bit data size is supported.
+    /// - `read`: Buffer for the received bytes.
+    /// - `write`: Data to write, at most 65535 bytes.
+    /// - `tx_stream`: DMA stream to use for the transmitter.
+    /// - `rx_stream`: DMA stream to use for the receiver.
+    pub fn transfer_dma(
+        &mut self,
+        read: &mut [u8],
+        write: &[u8],
+        tx_stream: DmaStream,
+        rx_stream: DmaStream,
+    ) -> Result<(), Error> {
+        let tx_request_input = R::tx_dma_request().ok_or(Error::NoDmaRequest)?;
+        let rx_request_input = R::rx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if write.len() > u16::MAX as usize || read.len() != write.len() {
+            return Err(Error::TransferSize);
+        }
+
+        if write.is_empty() {
+            return Ok(());
+        }
+
+        let rx_config = DmaStreamConfig {
+            request_input: rx_request_input,
+            transfer_direction: TransferDirection::PeripheralToMemory,
+            memory_data_size: DataSize::Byte,
+            peripheral_data_size: DataSize::Byte,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        let tx_config = DmaStreamConfig {
+            request_input: tx_request_input,
+            transfer_direction: TransferDirection::MemoryToPeripheral,
+            ..rx_config
+        };
+
+        rx_config.validate_memory_address(read.as_mut_ptr() as u32)?;
+        tx_config.validate_memory_address(write.as_ptr() as u32)?;
+        rx_stream.init(rx_config)?;
+        tx_stream.init(tx_config)?;
+
+        self.disable();
+
+        let regs = R::registers();
+        let cfg1 = regs.spi_cfg1.read();
+        let tx_dma_enable = cfg1.txdmaen().bit_is_set();
+        let rx_dma_enable = cfg1.rxdmaen().bit_is_set();
+
+        // The receiver stream has to be set up before the transmitter one, so no
+        // received data is lost.
+        regs.spi_cfg1.modify(|_, w| w.rxdmaen().set_bit());
+        rx_stream.start_transfer(
+            read.as_mut_ptr() as u32,
+            regs.spi2s_rxdr.as_ptr() as u32,
+            read.len(),
+        );
+        tx_stream.start_transfer(
+            write.as_ptr() as u32,
+            regs.spi2s_txdr.as_ptr() as u32,
+            write.len(),
+        );
+
+        regs.spi_cfg1.modify(|_, w| w.txdmaen().set_bit());
+
+        self.set_transfer_size(write.len() as u16);
+        self.start_transfer();
+
+        let result = self
+            .wait_dma_stream(tx_stream)
+            .and_then(|_| self.wait_dma_stream(rx_stream))
+            .and_then(|_| self.wait_end_of_transfer());
+        if result.is_err() {
+            rx_stream.stop_transfer();
+        }
+
+        self.disable();
+        regs.spi_cfg1
+            .modify(|_, w| w.txdmaen().bit(tx_dma_enable).rxdmaen().bit(rx_dma_enable));
+
+        result
+    }
+
+    /// Waits for a DMA stream to complete.
+    ///
+    /// The stream is stopped and an error is returned on a transfer error or if no
+    /// frame is transferred within the flag timeout.
+    fn wait_dma_stream(&mut self, stream: DmaStream) -> Result<(), Error> {
+        let mut start = Instant::now();
+        let mut remaining_transfers = stream.remaining_transfers();
+
+        // The timeout restarts whenever a frame is transferred.
+        while !stream.is_transfer_complete() {
+            if stream.is_transfer_error() {
+                stream.stop_transfer();
+                return Err(Error::DmaTransfer);
+            }
+
+            if stream.remaining_transfers() != remaining_transfers {
+                remaining_transfers = stream.remaining_transfers();
+                start = Instant::now();
+            } else if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                stream.stop_transfer();
+                return Err(Error::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the end of the transfer and clears the flag.
+    ///
+    /// See `wait_for` for the errors.
+    fn wait_end_of_transfer(&mut self) -> Result<(), Error> {
+        self.wait_for(Self::is_end_of_transfer)?;
         self.clear_end_of_transfer();
+
+        Ok(())
+    }
+
+    /// Waits for a flag to be set.
+    ///
+    /// Returns an error and disables the peripheral on overrun or underrun, or if the
+    /// flag isn't set within the flag timeout.
+    fn wait_for(&mut self, flag: impl Fn(&Self) -> bool) -> Result<(), Error> {
+        let start = Instant::now();
+        while !flag(self) {
+            self.check_errors()?;
+            if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                self.disable();
+                return Err(Error::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads bytes into a buffer using RX DMA, blocking until the buffer is filled.
+    ///
+    /// The peripheral is switched to simplex receiver mode with RX DMA enabled, so the
+    /// master generates the clock without transmit data. Automatic suspension in receive
+    /// mode (MASRX) is enabled, which stops the clock while the RxFIFO is full, so no
+    /// overrun can occur. Only 8-bit data size is supported.
+    /// - `buffer`: Buffer to fill, at most 65535 bytes.
+    /// - `stream`: DMA stream to use.
+    pub fn read_stream(&mut self, buffer: &mut [u8], stream: DmaStream) -> Result<(), Error> {
+        if buffer.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        self.start_rx_stream(buffer, stream, false)?;
+
+        let result = self
+            .wait_dma_stream(stream)
+            .and_then(|_| self.wait_end_of_transfer());
+        self.disable();
+
+        result
+    }
+
+    /// Starts reading bytes continuously into a buffer using RX DMA in circular mode.
+    ///
+    /// See `read_stream` for the peripheral settings. The transfer runs until
+    /// `stop_read_stream` is called, which restores the communication mode, RX DMA
+    /// and automatic suspension settings.
+    /// - `buffer`: Ring buffer to fill, 1-65535 bytes.
+    /// - `stream`: DMA stream to use.
+    ///
+    /// # Safety
+    ///
+    /// The DMA keeps writing into `buffer` after this function returns. The buffer
+    /// must not be accessed other than through the returned `CircularTransfer`, moved
+    /// or dropped until `stop_read_stream` has returned.
+    pub unsafe fn read_stream_circular(
+        &mut self,
+        buffer: &mut [u8],
+        stream: DmaStream,
+    ) -> Result<CircularTransfer, Error> {
+        if buffer.is_empty() || buffer.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        let settings = self.rx_stream_settings();
+        self.start_rx_stream(buffer, stream, true)?;
+        self.saved_rx_stream = Some(settings);
+
+        Ok(CircularTransfer::new(stream, buffer.len()))
+    }
+
+    /// Stops a continuous read started with `read_stream_circular`.
+    ///
+    /// The peripheral is disabled and the previous settings are restored, even if
+    /// the suspension times out.
+    pub fn stop_read_stream(&mut self, transfer: CircularTransfer) -> Result<(), Error> {
+        let regs = R::registers();
+        let result = self.suspend();
+        regs.spi2s_ifcr.write(|w| w.suspc().set_bit());
+
+        transfer.stream().stop_transfer();
+        self.disable();
+
+        if let Some(settings) = self.saved_rx_stream.take() {
+            self.restore_rx_stream_settings(settings);
+        }
+
+        result
+    }
+
+    /// Returns the settings changed by `start_rx_stream`.
+    fn rx_stream_settings(&self) -> RxStreamSettings {
+        let regs = R::registers();
+        RxStreamSettings {
+            rx_dma_enable: regs.spi_cfg1.read().rxdmaen().bit_is_set(),
+            communication_mode: regs.spi_cfg2.read().comm().bits(),
+            master_auto_suspend: regs.spi2s_cr1.read().masrx().bit_is_set(),
+        }
+    }
+
+    /// Restores settings saved with `rx_stream_settings`, the peripheral must be disabled.
+    fn restore_rx_stream_settings(&mut self, settings: RxStreamSettings) {
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg1
+                .modify(|_, w| w.rxdmaen().bit(settings.rx_dma_enable));
+            regs.spi_cfg2
+                .modify(|_, w| w.comm().bits(settings.communication_mode));
+        }
+        regs.spi2s_cr1
+            .modify(|_, w| w.masrx().bit(settings.master_auto_suspend));
+    }
+
+    /// Configures the peripheral and the DMA stream for receiving and starts the transfer.
+    fn start_rx_stream(
+        &mut self,
+        buffer: &mut [u8],
+        stream: DmaStream,
+        circular: bool,
+    ) -> Result<(), Error> {
+        let request_input = R::rx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::PeripheralToMemory,
+            memory_data_size: DataSize::Byte,
+            peripheral_data_size: DataSize::Byte,
+            circular,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(buffer.as_mut_ptr() as u32)?;
+        stream.init(config)?;
+
+        self.disable();
+
+        let regs = R::registers();
+        unsafe {
+            regs.spi_cfg1.modify(|_, w| w.rxdmaen().set_bit());
+            regs.spi_cfg2
+                .modify(|_, w| w.comm().bits(CommunicationMode::SimplexReceiver as u8));
+            regs.spi2s_cr1.modify(|_, w| w.masrx().set_bit());
+
+            // A transfer size of 0 lets the master receive endlessly.
+            let size = if circular { 0 } else { buffer.len() as u16 };
+            regs.spi_cr2.modify(|_, w| w.tsize().bits(size));
+        }
+
+        stream.start_transfer(
+            buffer.as_mut_ptr() as u32,
+            regs.spi2s_rxdr.as_ptr() as u32,
+            buffer.len(),
+        );
+
+        self.start_transfer();
+
+        Ok(())
     }
 
     /// Writes a byte to the TxFIFO.
@@ -273,13 +1122,25 @@ where
         }
     }
 
+    /// Writes a half-word to the TxFIFO.
+    pub fn write_tx_fifo_half_word(&mut self, half_word: u16) {
+        let regs = R::registers();
+        unsafe {
+            core::ptr::write_volatile(regs.spi2s_txdr.as_ptr() as *mut u16, half_word);
+        }
+    }
+
     /// Reads a byte from the RxFIFO.
     pub fn read_rx_fifo_byte(&mut self) -> u8 {
         let regs = R::registers();
         unsafe { core::ptr::read_volatile(regs.spi2s_rxdr.as_ptr() as *mut u8) }
     }
 
-    /// Sets the transfer size.
+    /// Sets the transfer size in frames.
+    ///
+    /// A size of 0 means an unlimited transfer that never signals the end of transfer.
+    /// All blocking transfer methods set the size to the exact number of frames and
+    /// return immediately for empty buffers, so they can't hang waiting for it.
     pub fn set_transfer_size(&mut self, size: u16) {
         let enabled = self.is_enabled();
         self.disable();
@@ -301,6 +1162,43 @@ where
         regs.spi2s_cr1.modify(|_, w| w.cstart().set_bit());
     }
 
+    /// Suspends an ongoing transfer in master mode at the next frame boundary, blocking.
+    ///
+    /// The peripheral stays enabled and keeps the remaining transfer size, so the
+    /// transfer can be continued with `resume`. Returns immediately if no transfer
+    /// is ongoing, because SUSP is only set while CSTART is set. Returns an error if
+    /// the suspension isn't acknowledged within the flag timeout.
+    pub fn suspend(&mut self) -> Result<(), Error> {
+        let regs = R::registers();
+        if regs.spi2s_cr1.read().cstart().bit_is_clear() {
+            return Ok(());
+        }
+
+        regs.spi2s_cr1.modify(|_, w| w.csusp().set_bit());
+
+        let start = Instant::now();
+        while regs.spi2s_sr.read().susp().bit_is_clear() {
+            if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                return Err(Error::Timeout);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns if the transfer is suspended.
+    pub fn is_suspended(&self) -> bool {
+        let regs = R::registers();
+        regs.spi2s_sr.read().susp().bit_is_set()
+    }
+
+    /// Resumes a transfer suspended with `suspend`.
+    pub fn resume(&mut self) {
+        let regs = R::registers();
+        regs.spi2s_ifcr.write(|w| w.suspc().set_bit());
+        regs.spi2s_cr1.modify(|_, w| w.cstart().set_bit());
+    }
+
     /// Enables the peripheral.
     pub fn enable(&mut self) {
         let regs = R::registers();
@@ -343,6 +1241,28 @@ where
         regs.spi2s_sr.read().eot().bit_is_set()
     }
 
+    /// Returns the number of frames in the RxFIFO that don't complete a packet yet,
+    /// range is 0-3.
+    ///
+    /// Only valid for data sizes of up to 16 bits. The hardware doesn't provide a
+    /// level for the TxFIFO, use `is_transmission_complete` to check if it is empty.
+    pub fn rx_fifo_level(&self) -> u8 {
+        let regs = R::registers();
+        regs.spi2s_sr.read().rxplvl().bits()
+    }
+
+    /// Returns if the TxFIFO is empty and the last frame was sent.
+    pub fn is_transmission_complete(&self) -> bool {
+        let regs = R::registers();
+        regs.spi2s_sr.read().txc().bit_is_set()
+    }
+
+    /// Returns the number of frames remaining in the current transfer.
+    pub fn remaining_transfer_size(&self) -> u16 {
+        let regs = R::registers();
+        regs.spi2s_sr.read().ctsize().bits()
+    }
+
     /// Returns if an overrun error has occurred.
     pub fn is_overrun_error(&self) -> bool {
         let regs = R::registers();
@@ -379,12 +1299,144 @@ where
         regs.spi2s_ifcr.write(|w| w.udrc().set_bit());
     }
 
+    /// Returns a device using a GPIO pin as chip select.
+    ///
+    /// The active level of the pin follows the configured SS polarity.
+    pub fn with_cs(self, cs: Pin) -> SpiDevice<R> {
+        SpiDevice::new(self, cs)
+    }
+
     /// Returns the register block.
     pub fn registers(&self) -> &'static RegisterBlock {
         R::registers()
     }
 }
 
+/// SPI bus with a GPIO pin as chip select for a single device.
+#[derive(Debug)]
+pub struct SpiDevice<R>
+where
+    R: Deref<Target = RegisterBlock>,
+{
+    /// SPI bus.
+    spi: Spi<R>,
+    /// Chip select pin.
+    cs: Pin,
+}
+
+impl<R> SpiDevice<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    /// Returns a device and sets the chip select pin to output in inactive state.
+    pub fn new(spi: Spi<R>, mut cs: Pin) -> Self {
+        let regs = R::registers();
+        let active_high = regs.spi_cfg2.read().ssiop().bit_is_set();
+        cs.set_output_state(!active_high);
+        cs.apply_mode(PinMode::Output);
+
+        Self { spi, cs }
+    }
+
+    /// Returns the SPI bus and the chip select pin.
+    pub fn release(self) -> (Spi<R>, Pin) {
+        (self.spi, self.cs)
+    }
+
+    /// Sets the chip select pin to active or inactive state.
+    fn set_cs_active(&mut self, active: bool) {
+        let regs = R::registers();
+        let active_high = regs.spi_cfg2.read().ssiop().bit_is_set();
+        self.cs.set_output_state(active == active_high);
+    }
+}
+
+// --------------------------- embedded-hal ---------------------------
+
+impl eh::spi::Error for Error {
+    fn kind(&self) -> eh::spi::ErrorKind {
+        match self {
+            Error::Overrun | Error::Underrun => eh::spi::ErrorKind::Overrun,
+            _ => eh::spi::ErrorKind::Other,
+        }
+    }
+}
+
+impl<R> eh::spi::ErrorType for Spi<R>
+where
+    R: Deref<Target = RegisterBlock>,
+{
+    type Error = Error;
+}
+
+/// Requires 8-bit data size and a FIFO threshold level of 1. Reads and writes use
+/// the methods matching the communication mode, transfers require full-duplex mode.
+/// Operations the communication mode doesn't allow return an error.
+impl<R> eh::spi::SpiBus for Spi<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        self.read_any_mode(words)
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.write_any_mode(words)
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        Spi::transfer(self, read, write)
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        Spi::transfer_in_place(self, words)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        // All methods block until the end of the transfer.
+        Ok(())
+    }
+}
+
+impl<R> eh::spi::ErrorType for SpiDevice<R>
+where
+    R: Deref<Target = RegisterBlock>,
+{
+    type Error = Error;
+}
+
+/// Chip select is active for all operations, see `SpiBus` for the requirements.
+impl<R> eh::spi::SpiDevice for SpiDevice<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    fn transaction(
+        &mut self,
+        operations: &mut [eh::spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.set_cs_active(true);
+
+        let result = operations
+            .iter_mut()
+            .try_for_each(|operation| match operation {
+                eh::spi::Operation::Read(words) => self.spi.read_any_mode(words),
+                eh::spi::Operation::Write(words) => self.spi.write_any_mode(words),
+                eh::spi::Operation::Transfer(read, write) => self.spi.transfer(read, write),
+                eh::spi::Operation::TransferInPlace(words) => self.spi.transfer_in_place(words),
+                eh::spi::Operation::DelayNs(ns) => {
+                    time::delay_us(ns.div_ceil(1000));
+                    Ok(())
+                }
+            });
+
+        // Chip select is released on errors as well, the operations return instead
+        // of blocking if the communication mode doesn't allow them.
+        self.set_cs_active(false);
+
+        result
+    }
+}
+
 // ---------------------------- Instance ------------------------------
 
 /// Trait for instance specific functions.
@@ -398,8 +1450,17 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
+
+    /// Returns the DMA request input for the transmitter, if available.
+    fn tx_dma_request() -> Option<DmaRequestInput>;
+
+    /// Returns the DMA request input for the receiver, if available.
+    fn rx_dma_request() -> Option<DmaRequestInput>;
 }
 
 // ------------------------------- SPI1 -------------------------------
@@ -433,8 +1494,22 @@ impl Instance for SPI1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.spi1rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.spi1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pll4_p_frequency()
+        rcc::spi1_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi1Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi1Rx)
     }
 }
 
@@ -469,8 +1544,22 @@ impl Instance for SPI2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.spi2rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.spi2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pll4_p_frequency()
+        rcc::spi23_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi2Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi2Rx)
     }
 }
 
@@ -505,8 +1594,22 @@ impl Instance for SPI3 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.spi3rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.spi3rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pll4_p_frequency()
+        rcc::spi23_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi3Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi3Rx)
     }
 }
 
@@ -541,8 +1644,22 @@ impl Instance for SPI4 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.spi4rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.spi4rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk2_frequency()
+        rcc::spi45_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi4Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi4Rx)
     }
 }
 
@@ -577,8 +1694,22 @@ impl Instance for SPI5 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.spi5rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.spi5rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk2_frequency()
+        rcc::spi45_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi5Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Spi5Rx)
     }
 }
 
@@ -613,7 +1744,61 @@ impl Instance for SPI6 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb5rstsetr.write(|w| w.spi6rst().set_bit());
+        rcc.rcc_apb5rstclrr.write(|w| w.spi6rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk5_frequency()
+        rcc::spi6_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        // SPI6 requests are only connected to the MDMA.
+        None
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        // SPI6 requests are only connected to the MDMA.
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prescaler_at_exact_frequency() {
+        assert_eq!(
+            ClockPrescaler::for_max_frequency(100000000, 50000000),
+            ClockPrescaler::Div2
+        );
+        assert_eq!(
+            ClockPrescaler::for_max_frequency(100000000, 25000000),
+            ClockPrescaler::Div4
+        );
+    }
+
+    #[test]
+    fn prescaler_above_boundary() {
+        // 100000001 Hz / 2 is slightly above 50 MHz.
+        assert_eq!(
+            ClockPrescaler::for_max_frequency(100000001, 50000000),
+            ClockPrescaler::Div4
+        );
+        assert_eq!(
+            ClockPrescaler::for_max_frequency(100000001, 25000000),
+            ClockPrescaler::Div8
+        );
+    }
+
+    #[test]
+    fn prescaler_unreachable_target() {
+        assert_eq!(
+            ClockPrescaler::for_max_frequency(100000000, 1000),
+            ClockPrescaler::Div256
+        );
     }
 }
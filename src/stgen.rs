@@ -1,8 +1,24 @@
 //! System timer generator.
 
+#[cfg(feature = "mpu-ca7")]
+use core::time::Duration;
+
 use crate::pac;
 use pac::stgenc::RegisterBlock;
 
+#[cfg(feature = "mpu-ca7")]
+use crate::irq::{self, Irqn};
+#[cfg(feature = "mpu-ca7")]
+use crate::rcc::per_ck_frequency;
+
+/// Tick handler function.
+#[cfg(feature = "mpu-ca7")]
+static mut TICK_HANDLER: Option<fn()> = None;
+
+/// Number of counter ticks between two periodic tick interrupts.
+#[cfg(feature = "mpu-ca7")]
+static mut TICK_RELOAD: u32 = 0;
+
 /// STGEN peripheral.
 #[derive(Debug, Default)]
 pub struct Stgen;
@@ -53,3 +69,57 @@ impl Stgen {
         unsafe { &(*pac::STGENC::ptr()) }
     }
 }
+
+// -------------------------- Periodic tick --------------------------
+
+/// Starts a periodic tick interrupt on the current core.
+///
+/// The tick is generated by the secure physical timer of the Cortex-A7, which counts
+/// the STGEN value. The timer interrupt is enabled in the GIC and the handler is called
+/// from the IRQ handler on each tick, before the user IRQ handler.
+/// - `period`: Interval between two ticks.
+/// - `handler`: Function to call on each tick.
+#[cfg(feature = "mpu-ca7")]
+pub fn start_periodic_tick(period: Duration, handler: fn()) {
+    let ticks = period.as_micros() as u64 * per_ck_frequency() as u64 / 1000000;
+    let reload = ticks.clamp(1, u32::MAX as u64) as u32;
+
+    critical_section::with(|_| unsafe {
+        TICK_HANDLER = Some(handler);
+        TICK_RELOAD = reload;
+    });
+
+    cortex_a7::regs::set_cntp_tval(reload);
+
+    // Enable the timer with the interrupt unmasked.
+    cortex_a7::regs::set_cntp_ctl(0b001);
+
+    irq::enable_irq(Irqn::SecurePhysicalTimer);
+}
+
+/// Stops the periodic tick interrupt on the current core.
+#[cfg(feature = "mpu-ca7")]
+pub fn stop_tick() {
+    cortex_a7::regs::set_cntp_ctl(0);
+    irq::disable_irq(Irqn::SecurePhysicalTimer);
+
+    critical_section::with(|_| unsafe {
+        TICK_HANDLER = None;
+    });
+}
+
+/// Re-arms the timer and calls the tick handler.
+///
+/// This function is called from the IRQ handler.
+#[cfg(feature = "mpu-ca7")]
+pub(crate) fn on_tick_interrupt() {
+    unsafe {
+        if let Some(handler) = TICK_HANDLER {
+            // Writing the timer value also clears the interrupt condition.
+            cortex_a7::regs::set_cntp_tval(TICK_RELOAD);
+            handler();
+        } else {
+            cortex_a7::regs::set_cntp_ctl(0);
+        }
+    }
+}
@@ -8,8 +8,13 @@ use core::task::Poll;
 use cfg_if::cfg_if;
 
 use crate::bitworker::bitmask;
+use crate::dma::{
+    CircularTransfer, DmaConfigError, DmaRequestInput, DmaStream, DmaStreamConfig,
+    TransferDirection,
+};
 use crate::pac;
 use crate::rcc;
+use crate::time::Instant;
 use pac::usart1::RegisterBlock;
 use pac::{USART1, USART2, USART3, USART4, USART5, USART6, USART7, USART8};
 
@@ -19,6 +24,8 @@ pub struct Usart<R>
 where
     R: Deref<Target = RegisterBlock>,
 {
+    /// Error of a partial read through `embedded_io`, returned by the next read.
+    pending_error: Option<Error>,
     /// Phantom register block.
     _regs: PhantomData<R>,
 }
@@ -47,6 +54,10 @@ pub type Usart7 = Usart<USART7>;
 /// Type alias for USART1.
 pub type Usart8 = Usart<USART8>;
 
+/// Timeout in milliseconds for the transmitter to make progress in blocking DMA
+/// transfers.
+const FLAG_TIMEOUT: u64 = 100;
+
 // ------------------------- Configuration ---------------------------
 
 /// Configuration settings.
@@ -68,6 +79,17 @@ pub struct UsartConfig {
     pub receiver_enable: bool,
     /// FIFO mode enable.
     pub fifo_mode: bool,
+    /// RS-485 driver enable output on the DE pin, disabled if `None`.
+    pub rs485: Option<Rs485Config>,
+    /// RTS hardware flow control, requesting data only when there is space to receive.
+    ///
+    /// Can't be combined with RS-485, because DE shares the pin with RTS. `init()` returns
+    /// `ConfigError::RtsWithRs485` for this combination.
+    pub rts_enable: bool,
+    /// CTS hardware flow control, transmitting only while CTS is asserted.
+    pub cts_enable: bool,
+    /// IrDA SIR encoding, disabled if `None`.
+    pub irda: Option<IrdaConfig>,
 }
 
 impl Default for UsartConfig {
@@ -85,10 +107,77 @@ impl Default for UsartConfig {
             transmitter_enable: false,
             receiver_enable: false,
             fifo_mode: true,
+            rs485: None,
+            rts_enable: false,
+            cts_enable: false,
+            irda: None,
+        }
+    }
+}
+
+impl UsartConfig {
+    /// Checks the configuration for settings that can't be combined.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.rts_enable && self.rs485.is_some() {
+            return Err(ConfigError::RtsWithRs485);
         }
+
+        if let Some(irda) = self.irda {
+            if irda.low_power && irda.prescaler == 0 {
+                return Err(ConfigError::IrdaPrescaler);
+            }
+        }
+
+        Ok(())
     }
 }
 
+/// RS-485 driver enable settings.
+///
+/// The DE pin is asserted by the hardware around each transmission.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Rs485Config {
+    /// Time between DE activation and the start bit in sample time units, range is 0-31.
+    pub assertion_time: u8,
+    /// Time between the end of the last stop bit and DE deactivation in sample time units,
+    /// range is 0-31.
+    pub deassertion_time: u8,
+    /// DE signal polarity.
+    pub polarity: DePolarity,
+}
+
+/// IrDA SIR settings.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IrdaConfig {
+    /// Low-power mode with pulses derived from the prescaled clock.
+    pub low_power: bool,
+    /// Kernel clock divider for the low-power pulse width, must not be 0.
+    ///
+    /// Only used in low-power mode, the divider is set to 1 in normal mode as required
+    /// by the reference manual.
+    pub prescaler: u8,
+}
+
+/// Driver enable signal polarity.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum DePolarity {
+    /// Active high.
+    High = 0b0,
+    /// Active low.
+    Low = 0b1,
+}
+
+/// LIN break detection length.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum LinBreakLength {
+    /// 10 bits.
+    Bits10 = 0b0,
+    /// 11 bits.
+    Bits11 = 0b1,
+}
+
 /// Parity.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Parity {
@@ -160,6 +249,16 @@ impl From<OverSampling> for bool {
 
 // ----------------------------- Errors -------------------------------
 
+/// Configuration errors.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConfigError {
+    /// RTS flow control and the RS-485 driver enable share the same pin.
+    RtsWithRs485,
+    /// IrDA low-power prescaler is 0.
+    IrdaPrescaler,
+}
+
 /// Errors
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -172,6 +271,22 @@ pub enum Error {
     Overrun,
     /// Noise error.
     Noise,
+    /// Instance has no DMA request line.
+    NoDmaRequest,
+    /// DMA transfer error.
+    DmaTransfer,
+    /// Transfer size is out of range, 1-65535 bytes are supported with DMA.
+    TransferSize,
+    /// Timeout waiting for the transfer to progress.
+    Timeout,
+    /// DMA stream configuration error.
+    DmaConfig(DmaConfigError),
+}
+
+impl From<DmaConfigError> for Error {
+    fn from(value: DmaConfigError) -> Self {
+        Error::DmaConfig(value)
+    }
 }
 
 // ------------------------- Implementation ---------------------------
@@ -182,11 +297,17 @@ where
 {
     /// Returns the peripheral instance.
     pub fn new() -> Self {
-        Self { _regs: PhantomData }
+        Self {
+            pending_error: None,
+            _regs: PhantomData,
+        }
     }
 
     /// Initializes the peripheral.
-    pub fn init(&mut self, config: UsartConfig) {
+    pub fn init(&mut self, config: UsartConfig) -> Result<(), ConfigError> {
+        config.validate()?;
+        self.pending_error = None;
+
         R::enable_clock();
 
         self.disable();
@@ -224,22 +345,110 @@ where
         });
 
         unsafe {
-            regs.cr2
-                .modify(|_, w| w.stop().bits(config.stop_bits.into()));
+            // LIN mode is only enabled by `init_lin()`.
+            regs.cr2.modify(|_, w| {
+                w.stop()
+                    .bits(config.stop_bits.into())
+                    .linen()
+                    .clear_bit()
+                    .lbdl()
+                    .clear_bit()
+            });
             regs.brr.write(|w| w.bits(brr));
         }
 
+        match config.rs485 {
+            Some(rs485) => unsafe {
+                regs.cr1.modify(|_, w| {
+                    w.deat()
+                        .bits(rs485.assertion_time.min(31))
+                        .dedt()
+                        .bits(rs485.deassertion_time.min(31))
+                });
+                regs.cr3.modify(|_, w| {
+                    w.dem()
+                        .set_bit()
+                        .dep()
+                        .bit(rs485.polarity == DePolarity::Low)
+                });
+            },
+            None => {
+                regs.cr3.modify(|_, w| w.dem().clear_bit());
+            }
+        }
+
+        regs.cr3.modify(|_, w| {
+            w.rtse()
+                .bit(config.rts_enable)
+                .ctse()
+                .bit(config.cts_enable)
+        });
+
+        match config.irda {
+            Some(irda) => {
+                // PSC must be 1 in normal mode.
+                let prescaler = if irda.low_power { irda.prescaler } else { 1 };
+                unsafe {
+                    regs.gtpr.modify(|_, w| w.psc().bits(prescaler));
+                }
+                regs.cr3
+                    .modify(|_, w| w.irlp().bit(irda.low_power).iren().set_bit());
+            }
+            None => {
+                regs.cr3.modify(|_, w| w.iren().clear_bit());
+            }
+        }
+
         self.enable();
 
         // Discard any received data.
         while self.read_ready().unwrap_or(false) {
             self.read_one().ok();
         }
+
+        Ok(())
+    }
+
+    /// Switches the initialized peripheral to LIN mode.
+    ///
+    /// One stop bit is used and the modes that can't be combined with LIN, e.g.
+    /// synchronous, smartcard, half-duplex and IrDA, are disabled.
+    pub fn init_lin(&mut self, break_length: LinBreakLength) {
+        let enabled = self.is_enabled();
+        self.disable();
+
+        let regs = R::registers();
+
+        unsafe {
+            regs.cr2.modify(|_, w| {
+                w.stop()
+                    .bits(StopBits::Bits1.into())
+                    .clken()
+                    .clear_bit()
+                    .lbdl()
+                    .bit(break_length == LinBreakLength::Bits11)
+                    .linen()
+                    .set_bit()
+            });
+        }
+        regs.cr3
+            .modify(|_, w| w.scen().clear_bit().hdsel().clear_bit().iren().clear_bit());
+
+        if enabled {
+            self.enable();
+        }
+    }
+
+    /// Sends a break character after the current transmission.
+    pub fn send_break(&mut self) {
+        let regs = R::registers();
+        regs.rqr.write(|w| w.sbkrq().set_bit());
     }
 
     /// Deinitializes the peripheral.
     pub fn deinit(&mut self) {
         self.disable();
+        R::reset();
         R::disable_clock();
     }
 
@@ -249,38 +458,55 @@ where
     }
 
     /// Returns one byte from the receiver, blocks if none available.
+    ///
+    /// On a parity, framing or noise error, the erroneous byte is discarded. On an overrun,
+    /// the byte in the receiver is still valid and returned by the next read.
     pub fn read_one(&mut self) -> Result<u8, Error> {
         while !self.read_ready()? {}
 
-        if self.is_parity_error() {
+        self.take_received()
+    }
+
+    /// Returns the received byte after checking the error flags.
+    fn take_received(&mut self) -> Result<u8, Error> {
+        let regs = R::registers();
+
+        let error = if self.is_parity_error() {
             self.clear_parity_error();
-            return Err(Error::Parity);
+            Error::Parity
         } else if self.is_framing_error() {
             self.clear_framing_error();
-            return Err(Error::Framing);
+            Error::Framing
         } else if self.is_overrun_error() {
             self.clear_overrun_error();
             return Err(Error::Overrun);
         } else if self.is_noise_detected() {
             self.clear_noise_detected();
-            return Err(Error::Noise);
-        }
+            Error::Noise
+        } else {
+            return Ok((regs.rdr.read().bits() & 0xFF) as u8);
+        };
 
-        let regs = R::registers();
-        Ok((regs.rdr.read().bits() & 0xFF) as u8)
+        // Discard the erroneous byte, so it's not returned as valid data by the next read.
+        regs.rdr.read();
+
+        Err(error)
     }
 
     /// Writes received bytes into a buffer, blocks if none available.
     /// Returns the total number of read bytes.
-    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
-        while !self.read_ready()? {}
+    ///
+    /// On error, the number of valid bytes in the buffer preceding the erroneous byte
+    /// is returned together with the error.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, (Error, usize)> {
+        while !self.read_ready().map_err(|error| (error, 0))? {}
 
         let mut count = 0;
 
         for byte in buffer.iter_mut() {
-            *byte = self.read_one()?;
+            *byte = self.read_one().map_err(|error| (error, count))?;
             count += 1;
-            if !self.read_ready()? {
+            if !self.read_ready().map_err(|error| (error, count))? {
                 break;
             }
         }
@@ -288,9 +514,58 @@ where
         Ok(count)
     }
 
-    /// Returns one byte from the receiver asynchronuously.
-    pub async fn read_one_async(&mut self) -> Result<u8, Error> {
-        self.wait_for_receiver_not_empty_async().await;
+    /// Receives a frame of variable length into a buffer using DMA, blocking.
+    ///
+    /// The transfer ends when the buffer is full or the line becomes idle after at least
+    /// one byte was received. Returns the number of received bytes.
+    /// - `stream`: DMA stream to use.
+    /// - `buffer`: Buffer for the frame, its length is the maximum frame size.
+    pub fn receive_frame_dma(
+        &mut self,
+        stream: DmaStream,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        let request_input = R::rx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let regs = R::registers();
+
+        regs.icr.write(|w| w.idlecf().set_bit());
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::PeripheralToMemory,
+            memory_increment: true,
+            bufferable_transfers: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(buffer.as_mut_ptr() as u32)?;
+        stream.init(config)?;
+        regs.cr3.modify(|_, w| w.dmar().set_bit());
+        stream.start_transfer(
+            buffer.as_mut_ptr() as u32,
+            regs.rdr.as_ptr() as u32,
+            buffer.len(),
+        );
+
+        let mut result = Ok(());
+
+        while !stream.is_transfer_complete() && !self.is_idle() {
+            if stream.is_transfer_error() {
+                result = Err(Error::DmaTransfer);
+                break;
+            }
+        }
+
+        stream.stop_transfer();
+        regs.cr3.modify(|_, w| w.dmar().clear_bit());
+        regs.icr.write(|w| w.idlecf().set_bit());
+
+        result?;
 
         if self.is_parity_error() {
             self.clear_parity_error();
@@ -306,8 +581,134 @@ where
             return Err(Error::Noise);
         }
 
+        Ok(buffer.len() - stream.remaining_transfers() as usize)
+    }
+
+    /// Starts receiving bytes continuously into a buffer using DMA in circular mode.
+    ///
+    /// The transfer runs until `stop_read_dma` is called. The position of the next
+    /// byte to be written is returned by `CircularTransfer::write_index`.
+    /// - `buffer`: Ring buffer to fill, 1-65535 bytes.
+    /// - `stream`: DMA stream to use.
+    ///
+    /// # Safety
+    ///
+    /// The DMA keeps writing into `buffer` after this function returns. The buffer
+    /// must not be accessed other than through the returned `CircularTransfer`, moved
+    /// or dropped until `stop_read_dma` has returned.
+    pub unsafe fn read_dma_circular(
+        &mut self,
+        buffer: &mut [u8],
+        stream: DmaStream,
+    ) -> Result<CircularTransfer, Error> {
+        let request_input = R::rx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if buffer.is_empty() || buffer.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
         let regs = R::registers();
-        Ok((regs.rdr.read().bits() & 0xFF) as u8)
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::PeripheralToMemory,
+            memory_increment: true,
+            circular: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(buffer.as_mut_ptr() as u32)?;
+        stream.init(config)?;
+        regs.cr3.modify(|_, w| w.dmar().set_bit());
+        stream.start_transfer(
+            buffer.as_mut_ptr() as u32,
+            regs.rdr.as_ptr() as u32,
+            buffer.len(),
+        );
+
+        Ok(CircularTransfer::new(stream, buffer.len()))
+    }
+
+    /// Stops a continuous reception started with `read_dma_circular`.
+    pub fn stop_read_dma(&mut self, transfer: CircularTransfer) {
+        let regs = R::registers();
+        transfer.stream().stop_transfer();
+        regs.cr3.modify(|_, w| w.dmar().clear_bit());
+    }
+
+    /// Writes bytes from a buffer using DMA, blocking until the transfer is complete.
+    /// - `data`: Data to write, at most 65535 bytes.
+    /// - `stream`: DMA stream to use.
+    ///
+    /// Returns an error if no byte is transmitted within the timeout, e.g. when the
+    /// transmitter is held off by CTS.
+    pub fn write_dma(&mut self, data: &[u8], stream: DmaStream) -> Result<(), Error> {
+        let request_input = R::tx_dma_request().ok_or(Error::NoDmaRequest)?;
+
+        if data.len() > u16::MAX as usize {
+            return Err(Error::TransferSize);
+        }
+
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let regs = R::registers();
+
+        let config = DmaStreamConfig {
+            request_input,
+            transfer_direction: TransferDirection::MemoryToPeripheral,
+            memory_increment: true,
+            ..Default::default()
+        };
+
+        config.validate_memory_address(data.as_ptr() as u32)?;
+        stream.init(config)?;
+        regs.icr.write(|w| w.tccf().set_bit());
+        regs.cr3.modify(|_, w| w.dmat().set_bit());
+        stream.start_transfer(data.as_ptr() as u32, regs.tdr.as_ptr() as u32, data.len());
+
+        let mut result = Ok(());
+        let mut start = Instant::now();
+        let mut remaining_transfers = stream.remaining_transfers();
+
+        // The timeout restarts whenever a byte is transferred.
+        while !stream.is_transfer_complete() {
+            if stream.is_transfer_error() {
+                result = Err(Error::DmaTransfer);
+                break;
+            }
+
+            if stream.remaining_transfers() != remaining_transfers {
+                remaining_transfers = stream.remaining_transfers();
+                start = Instant::now();
+            } else if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                result = Err(Error::Timeout);
+                break;
+            }
+        }
+
+        if result.is_ok() {
+            let start = Instant::now();
+            while !self.is_transfer_complete() {
+                if start.is_elapsed_millis(FLAG_TIMEOUT) {
+                    result = Err(Error::Timeout);
+                    break;
+                }
+            }
+        }
+
+        stream.stop_transfer();
+        regs.cr3.modify(|_, w| w.dmat().clear_bit());
+
+        result
+    }
+
+    /// Returns one byte from the receiver asynchronuously.
+    pub async fn read_one_async(&mut self) -> Result<u8, Error> {
+        self.wait_for_receiver_not_empty_async().await;
+
+        self.take_received()
     }
 
     /// Writes bytes from a buffer, blocking.
@@ -412,6 +813,36 @@ where
         regs.isr.read().nf().bit_is_set()
     }
 
+    /// Clears the idle line detected flag.
+    pub fn clear_idle(&mut self) {
+        let regs = R::registers();
+        regs.icr.write(|w| w.idlecf().set_bit());
+    }
+
+    /// Returns if a LIN break was detected.
+    pub fn is_break_detected(&self) -> bool {
+        let regs = R::registers();
+        regs.isr.read().lbdf().bit_is_set()
+    }
+
+    /// Clears the LIN break detected flag.
+    pub fn clear_break_detected(&mut self) {
+        let regs = R::registers();
+        regs.icr.write(|w| w.lbdcf().set_bit());
+    }
+
+    /// Returns if the CTS input toggled.
+    pub fn is_cts_changed(&self) -> bool {
+        let regs = R::registers();
+        regs.isr.read().ctsif().bit_is_set()
+    }
+
+    /// Clears the CTS changed flag.
+    pub fn clear_cts_changed(&mut self) {
+        let regs = R::registers();
+        regs.icr.write(|w| w.ctscf().set_bit());
+    }
+
     /// Clears a parity error.
     pub fn clear_parity_error(&mut self) {
         let regs = R::registers();
@@ -477,12 +908,144 @@ where
         .await
     }
 
+    /// Asynchronuously wait for an idle line.
+    ///
+    /// The flag is only set after at least one byte was received, it is not cleared.
+    pub async fn wait_for_idle_async(&self) {
+        poll_fn(|cx| {
+            let regs = R::registers();
+            if regs.isr.read().idle().bit_is_clear() {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await
+    }
+
     /// Returns the register block.
     pub fn registers(&self) -> &'static RegisterBlock {
         R::registers()
     }
 }
 
+// --------------------------- embedded-io ----------------------------
+
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            Error::Parity | Error::Framing | Error::Noise => embedded_io::ErrorKind::InvalidData,
+            Error::NoDmaRequest => embedded_io::ErrorKind::Unsupported,
+            _ => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl<R> embedded_io::ErrorType for Usart<R>
+where
+    R: Deref<Target = RegisterBlock>,
+{
+    type Error = Error;
+}
+
+/// If an error occurs after some bytes were read, these bytes are returned and the error
+/// is reported by the next call. The erroneous byte itself is discarded, see `read_one()`.
+impl<R> embedded_io::Read for Usart<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        match Usart::read(self, buf) {
+            Ok(count) => Ok(count),
+            Err((error, 0)) => Err(error),
+            Err((error, count)) => {
+                self.pending_error = Some(error);
+                Ok(count)
+            }
+        }
+    }
+}
+
+impl<R> embedded_io::Write for Usart<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Usart::write(self, buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        while !self.is_transfer_complete() {}
+
+        Ok(())
+    }
+}
+
+/// If an error occurs after some bytes were read, these bytes are returned and the error
+/// is reported by the next call. The erroneous byte itself is discarded, see `read_one()`.
+impl<R> embedded_io_async::Read for Usart<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if let Some(error) = self.pending_error.take() {
+            return Err(error);
+        }
+
+        buf[0] = self.read_one_async().await?;
+        let mut count = 1;
+
+        // Bytes already in the receiver are returned without waiting for more.
+        for byte in buf[1..].iter_mut() {
+            if !self.read_ready()? {
+                break;
+            }
+            match self.read_one() {
+                Ok(value) => *byte = value,
+                Err(error) => {
+                    self.pending_error = Some(error);
+                    break;
+                }
+            }
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+impl<R> embedded_io_async::Write for Usart<R>
+where
+    R: Deref<Target = RegisterBlock> + Instance,
+{
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_async(buf).await;
+
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_transfer_complete_async().await;
+
+        Ok(())
+    }
+}
+
 // ---------------------------- Instance ------------------------------
 
 /// Trait for instance specific functions.
@@ -496,8 +1059,17 @@ pub trait Instance {
     /// Disables the clock.
     fn disable_clock();
 
+    /// Resets the peripheral via the RCC reset register.
+    fn reset();
+
     /// Returns the clock frequency in Hz.
     fn clock_frequency() -> f32;
+
+    /// Returns the DMA request input for the transmitter, if available.
+    fn tx_dma_request() -> Option<DmaRequestInput>;
+
+    /// Returns the DMA request input for the receiver, if available.
+    fn rx_dma_request() -> Option<DmaRequestInput>;
 }
 
 // ------------------------------ USART1 ------------------------------
@@ -531,8 +1103,24 @@ impl Instance for USART1 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb5rstsetr.write(|w| w.usart1rst().set_bit());
+        rcc.rcc_apb5rstclrr.write(|w| w.usart1rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk5_frequency()
+        rcc::usart1_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        // USART1 requests are only connected to the MDMA.
+        None
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        // USART1 requests are only connected to the MDMA.
+        None
     }
 }
 
@@ -567,8 +1155,22 @@ impl Instance for USART2 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.usart2rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.usart2rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart24_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart2Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart2Rx)
     }
 }
 
@@ -603,8 +1205,22 @@ impl Instance for USART3 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.usart3rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.usart3rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart35_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart3Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart3Rx)
     }
 }
 
@@ -639,8 +1255,22 @@ impl Instance for USART4 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.uart4rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.uart4rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart24_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart4Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart4Rx)
     }
 }
 
@@ -675,8 +1305,22 @@ impl Instance for USART5 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.uart5rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.uart5rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart35_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart5Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart5Rx)
     }
 }
 
@@ -711,8 +1355,22 @@ impl Instance for USART6 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb2rstsetr.write(|w| w.usart6rst().set_bit());
+        rcc.rcc_apb2rstclrr.write(|w| w.usart6rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk2_frequency()
+        rcc::usart6_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart6Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Usart6Rx)
     }
 }
 
@@ -747,8 +1405,22 @@ impl Instance for USART7 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.uart7rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.uart7rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart78_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart7Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart7Rx)
     }
 }
 
@@ -783,7 +1455,21 @@ impl Instance for USART8 {
         }
     }
 
+    fn reset() {
+        let rcc = unsafe { &(*pac::RCC::ptr()) };
+        rcc.rcc_apb1rstsetr.write(|w| w.uart8rst().set_bit());
+        rcc.rcc_apb1rstclrr.write(|w| w.uart8rst().set_bit());
+    }
+
     fn clock_frequency() -> f32 {
-        rcc::pclk1_frequency()
+        rcc::uart78_kernel_frequency()
+    }
+
+    fn tx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart8Tx)
+    }
+
+    fn rx_dma_request() -> Option<DmaRequestInput> {
+        Some(DmaRequestInput::Uart8Rx)
     }
 }